@@ -0,0 +1,253 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use legion::*;
+use rand::thread_rng;
+use rand::Rng;
+
+use crate::core::*;
+use crate::inspection::*;
+use crate::people::*;
+use crate::production::*;
+use crate::resources::*;
+use crate::storage::*;
+
+/// Какая доля склада без присмотра пропадает за смену (из 1000)
+pub static SHRINKAGE_PERMILLE: usize = 3;
+
+/// Ниже какого среднего настроения со складов начинают тащить
+pub static THEFT_MOOD: usize = 5;
+
+/// Насколько растет воровство за каждую единицу настроения ниже порога (из 1000)
+pub static THEFT_PERMILLE_PER_MOOD: usize = 1;
+
+/// Сколько объема склада пересчитывает функционер за трудочас
+pub static AUDIT_VOLUME_PER_BP: usize = 500;
+
+/// Потери со складов, о которых начальство еще не знает
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Shrinkage(pub HashMap<Resource, RealUnits>);
+
+/// Метка задания на инвентаризацию
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InventoryAudit;
+
+/// Итоги последней инвентаризации
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct AuditResult {
+    pub recorded: HashMap<Resource, RealUnits>, // сколько числилось по учету
+    pub counted: HashMap<Resource, RealUnits>, // сколько насчитали на самом деле
+}
+
+impl AuditResult {
+    /// Недостача по каждому ресурсу
+    pub fn shortage(&self) -> HashMap<Resource, RealUnits> {
+        self.recorded
+            .iter()
+            .map(|(res, recorded)| {
+                let counted = self.counted.get(res).map(|c| c.0).unwrap_or(0);
+                (*res, RealUnits(recorded.0.saturating_sub(counted)))
+            })
+            .filter(|(_, amount)| amount.0 > 0)
+            .collect()
+    }
+}
+
+/// Сколько чего числится на складах по учету:
+/// то, что есть, плюс то, что пропало незаметно
+pub fn recorded_stock(
+    world: &mut World,
+    shrinkage: &Shrinkage,
+) -> HashMap<Resource, RealUnits> {
+    let mut result = what_we_have(world);
+    for (res, lost) in shrinkage.0.iter() {
+        *result.entry(*res).or_insert(RealUnits(0)) += *lost;
+    }
+    result
+}
+
+/// Списать со склада пропавшее так, чтобы по учету оно продолжало числиться
+/// до ближайшей инвентаризации. Возвращает, сколько на самом деле пропало.
+pub fn lose_from_storage(
+    world: &mut World,
+    resources: &mut Resources,
+    res: Resource,
+    amount: RealUnits,
+) -> RealUnits {
+    let rest = writeoff(world, res, amount);
+    let lost = RealUnits(amount.0.saturating_sub(rest.0));
+    let mut shrinkage = resources
+        .get::<Shrinkage>()
+        .map(|s| s.clone())
+        .unwrap_or_default();
+    *shrinkage.0.entry(res).or_insert(RealUnits(0)) += lost;
+    resources.insert(shrinkage);
+    lost
+}
+
+/// Какая доля склада пропадает за смену (из 1000).
+/// Без кладовщиков растаскивают, недовольные тащат больше.
+pub fn loss_permille(
+    volume: usize,
+    storekeepers: usize,
+    avg_mood: usize,
+) -> usize {
+    let required = storekeepers_required(volume);
+    let unsupervised = required.saturating_sub(storekeepers);
+    let shrinkage = (SHRINKAGE_PERMILLE * unsupervised)
+        .checked_div(required)
+        .unwrap_or(0);
+    let theft = THEFT_MOOD.saturating_sub(avg_mood) * THEFT_PERMILLE_PER_MOOD;
+    shrinkage + theft
+}
+
+/// Усушка, утруска и хищения за смену.
+/// Пропавшее списывается со склада, но по учету продолжает числиться.
+pub fn shrinkage_tick(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    let mut people_query = <&Mood>::query();
+    let people = people_query.iter(world).count();
+    let avg_mood = block_mood(world)
+        .checked_div(people)
+        .unwrap_or(MOOD_MAX as usize);
+    let permille = loss_permille(
+        stored_volume(world),
+        storekeepers(world).len(),
+        avg_mood,
+    );
+    if permille == 0 {
+        return;
+    }
+    let mut rng = thread_rng();
+    for (res, amount) in what_we_have(world) {
+        // дробные потери набегают с вероятностью
+        let mut loss = amount.0 * permille / 1000;
+        if rng.gen_range(0..1000) < amount.0 * permille % 1000 {
+            loss += 1;
+        }
+        if loss == 0 {
+            continue;
+        }
+        lose_from_storage(world, resources, res, RealUnits(loss));
+    }
+}
+
+/// Пересчитать склады и закрыть учет: недостача становится известна и списывается
+pub fn close_books(
+    world: &mut World,
+    resources: &mut Resources,
+) -> AuditResult {
+    let shrinkage = resources
+        .get::<Shrinkage>()
+        .map(|s| s.clone())
+        .unwrap_or_default();
+    let result = AuditResult {
+        recorded: recorded_stock(world, &shrinkage),
+        counted: what_we_have(world),
+    };
+    resources.insert(Shrinkage::default());
+    result
+}
+
+/// Пересчет склада: партийный функционер 1 разряда, трудоемкость по объему
+pub fn audit_requirements(
+    volume: usize,
+) -> HashSet<TaskMeta> {
+    [
+        TaskMeta {
+            prof: Profession::Party,
+            tier: Tier::T1,
+            bp: BuildPower((volume / AUDIT_VOLUME_PER_BP).max(1)),
+            stationary: Stationary::None,
+        },
+    ].iter().cloned().collect()
+}
+
+/// Назначить инвентаризацию. Если она уже идет - вернуть ее.
+pub fn order_audit(
+    world: &mut World,
+) -> Entity {
+    let mut query = <(Entity, &InventoryAudit)>::query();
+    if let Some((audit, _)) = query.iter(world).next() {
+        return *audit;
+    }
+    let volume = stored_volume(world);
+    world.push((
+        InventoryAudit,
+        TaskPriority(0),
+        TaskStatus::Constructing,
+        task_meta2progress(audit_requirements(volume)),
+    ))
+}
+
+/// Завершить пересчитанную инвентаризацию и записать итоги
+pub fn finish_audits(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    let mut query = <(Entity, &InventoryAudit, &TaskStatus)>::query();
+    let finished: Vec<Entity> = query
+        .iter(world)
+        .filter(|(_, _, status)| **status == TaskStatus::Ready)
+        .map(|(e, _, _)| *e)
+        .collect();
+    if finished.is_empty() {
+        return;
+    }
+    for audit in finished {
+        world.remove(audit);
+    }
+    let result = close_books(world, resources);
+    let shortage: usize = result.shortage().values().map(|a| a.0).sum();
+    report(resources, format!("Инвентаризация окончена, недостача: {} ед.", shortage));
+    resources.insert(result);
+}
+
+/// Прогресс идущей инвентаризации
+pub fn audit_in_progress(
+    world: &World,
+) -> Option<TaskProgress> {
+    let mut query = <(&InventoryAudit, &TaskProgress)>::query();
+    query
+        .iter(world)
+        .next()
+        .map(|(_, progress)| progress.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::area::*;
+
+    #[test]
+    fn storekeepers_stop_shrinkage() {
+        let volume = 2 * STOREKEEPER_VOLUME;
+        assert_eq!(loss_permille(volume, 0, THEFT_MOOD), SHRINKAGE_PERMILLE);
+        assert_eq!(loss_permille(volume, 1, THEFT_MOOD), SHRINKAGE_PERMILLE / 2);
+        assert_eq!(loss_permille(volume, 2, THEFT_MOOD), 0);
+        assert_eq!(loss_permille(volume, 2, THEFT_MOOD - 2), 2 * THEFT_PERMILLE_PER_MOOD);
+        assert_eq!(loss_permille(0, 0, THEFT_MOOD), 0);
+    }
+
+    #[test]
+    fn lost_goods_show_up_at_audit() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let stock = install_germ(&mut world, Germ::GermT2, AreaType::Party);
+        if let Some(mut entry) = world.entry(stock) {
+            entry.add_component(TaskStatus::Ready);
+        }
+        put_resource(&mut world, Resource::Polymer, RealUnits(30));
+        assert_eq!(lose_from_storage(&mut world, &mut resources, Resource::Polymer, RealUnits(10)), RealUnits(10));
+        // больше, чем есть, не пропадет
+        assert_eq!(lose_from_storage(&mut world, &mut resources, Resource::Polymer, RealUnits(50)), RealUnits(20));
+        let audit = close_books(&mut world, &mut resources);
+        assert_eq!(audit.recorded[&Resource::Polymer], RealUnits(30));
+        assert_eq!(audit.shortage()[&Resource::Polymer], RealUnits(30));
+        assert_eq!(resources.get::<Shrinkage>().map(|s| s.clone()), Some(Shrinkage::default()));
+    }
+}
@@ -1,4 +1,5 @@
 use std::{
+    fmt,
     sync::mpsc,
     collections::{
         HashMap,
@@ -15,13 +16,16 @@ use eframe::{
         Direction,
         SidePanel,
         Button,
+        DragValue,
         ImageButton,
         CentralPanel,
         CollapsingHeader,
+        ScrollArea,
         Vec2,
         Rgba,
         Color32,
         Layout,
+        Ui,
         vec2,
     },
     epi,
@@ -34,6 +38,22 @@ use crate::production::*;
 use crate::resources::*;
 use crate::storage::*;
 use crate::people::*;
+use crate::squad::*;
+use crate::items::*;
+use crate::recipes::*;
+use crate::mood::*;
+use crate::unrest::*;
+use crate::housing::*;
+use crate::experience::*;
+use crate::education::*;
+use crate::population::*;
+use crate::refugees::*;
+use crate::trust::*;
+use crate::quota::*;
+use crate::inspection::*;
+use crate::accounting::*;
+use crate::coupons::*;
+use crate::research::*;
 use crate::turn::*;
 use crate::area::*;
 use crate::assets::{
@@ -44,6 +64,11 @@ use crate::assets::{
 use crate::queries::{
     who_take_place,
     what_take_place,
+    roster,
+    comrad_status,
+    all_comrad_statuses,
+    ComradStatus,
+    RosterEntry,
 };
 
 fn init_colony(world: &mut World) {
@@ -76,76 +101,153 @@ fn init_colony(world: &mut World) {
         AreaType::Science,
     );
 
-    let cell_sciencists = install_germ(
-        world,
-        Germ::GermT1,
-        AreaType::Living,
-    );
-    spawn_comrad(
-        world,
-        Profession::Scientist,
-        Tier::T1,
-        cell_sciencists,
-    );
-
-    // Жилячейки
-    for _ in 0..33 {
+    // Жилячейки. В них заселяемся сразу, так что они уже поставлены.
+    for _ in 0..34 {
         let cell = install_germ(
             world,
             Germ::GermT1,
             AreaType::Living,
         );
-        for _ in 0..3 {
-            spawn_comrad(
-                world,
-                Profession::Worker,
-                Tier::T1,
-                cell,
-            );
+        if let Some(mut entry) = world.entry(cell) {
+            entry.add_component(TaskStatus::Ready);
+            entry.remove_component::<TaskProgress>();
         }
     };
-
-    // Ресурсы
-    assert_eq!(
-        RealUnits(0),
-        put_resource(
-            world,
-            Resource::Concentrat,
-            RealUnits(1000),
-        ),
-    );
-    assert_eq!(
-        RealUnits (0),
-        put_resource(
-            world,
-            Resource::ScrapT1,
-            RealUnits(50),
-        )
-    );
-    assert_eq!(
-        RealUnits (0),
-        put_resource(
-            world,
-            Resource::ScrapT2,
-            RealUnits(40),
-        )
+    // Заселяем равномерно, каждого в наименее тесную
+    let _ = settle_comrad(
+        world,
+        Profession::Scientist,
+        Tier::T1,
     );
-    assert_eq!(
-        RealUnits (0),
-        put_resource(
+    for _ in 0..99 {
+        let _ = settle_comrad(
             world,
-            Resource::Polymer,
-            RealUnits(30),
-        )
-    );
+            Profession::Worker,
+            Tier::T1,
+        );
+    }
+
+    // Ресурсы
+    for (resource, amount) in INITIAL_STOCK.iter() {
+        assert_eq!(
+            RealUnits(0),
+            put_resource(
+                world,
+                *resource,
+                RealUnits(*amount),
+            ),
+        );
+    }
+}
+
+/// Подсказка о настроении комрада: итог и из чего он сложился
+fn mood_tooltip(
+    ui: &mut Ui,
+    world: &World,
+    comrad: Entity,
+) {
+    if let Some(Mood(mood)) = world
+        .entry_ref(comrad)
+        .ok()
+        .and_then(|entry| entry.get_component::<Mood>().ok().copied())
+    {
+        ui.label(format!("Настроение: {} из {}", mood, MOOD_MAX));
+    }
+    for (factor, value) in mood_breakdown(world, comrad) {
+        ui.label(format!("{}: {:+}", factor, value));
+    }
+}
+
+/// Личное дело комрада
+fn comrad_card(
+    ui: &mut Ui,
+    world: &World,
+    comrad: Entity,
+) {
+    let entry = match world.entry_ref(comrad) {
+        Ok(entry) => entry,
+        Err(_) => return,
+    };
+    if let Ok(Name(name)) = entry.get_component::<Name>() {
+        ui.heading(name);
+    }
+    if let (Ok(sex), Ok(age)) = (entry.get_component::<Sex>(), entry.get_component::<Age>()) {
+        ui.label(format!("{}, {} лет", sex, age.years()));
+    }
+    if let (Ok(prof), Ok(tier)) = (entry.get_component::<Profession>(), entry.get_component::<Tier>()) {
+        ui.label(format!("{} {}", prof, tier));
+    }
+    if let Ok(wallet) = entry.get_component::<Wallet>() {
+        ui.label(format!("Талонов: {}, зарплата {} из {}", wallet.coupons, wallet.wage, wallet.expected));
+    }
+    ui.label(format!("Статус: {}", comrad_status(world, comrad)));
+    if let Ok(tier) = entry.get_component::<Tier>() {
+        let exp = entry
+            .get_component::<Experience>()
+            .ok()
+            .copied()
+            .unwrap_or_default();
+        match experience_for_promotion(*tier) {
+            Some(required) => ui.label(format!("Опыт: {} из {}", exp.0, required.0)),
+            None => ui.label(format!("Опыт: {}", exp.0)),
+        };
+    }
+    if let Ok(BelongsToRoom(room)) = entry.get_component::<BelongsToRoom>() {
+        if let Some(atype) = world
+            .entry_ref(*room)
+            .ok()
+            .and_then(|room| room.get_component::<AreaType>().ok().copied())
+        {
+            ui.label(format!("Проживает: {}", atype));
+        }
+    }
+    if let Ok(Satiety(sat)) = entry.get_component::<Satiety>() {
+        ui.label(format!("Сытость: {}", sat));
+    }
+    if let Ok(Mood(mood)) = entry.get_component::<Mood>() {
+        ui.label(format!("Настроение: {} из {}", mood, MOOD_MAX))
+            .on_hover_ui(|ui| mood_tooltip(ui, world, comrad));
+    }
+    if let Ok(in_squad) = entry.get_component::<InSquad>() {
+        if let Some(squad) = world
+            .entry_ref(in_squad.squad)
+            .ok()
+            .and_then(|squad| squad.get_component::<Squad>().ok().cloned())
+        {
+            ui.label(format!("Отряд: {}, {}", squad.name, in_squad.role));
+        }
+    }
+    if let Some(items) = equipped_items(world).get(&comrad) {
+        for (_, item, tier, durability) in items {
+            ui.label(format!("Снаряжение: {} {}, {}", item, tier, durability));
+        }
+    }
+    if let Ok(Bio(bio)) = entry.get_component::<Bio>() {
+        ui.separator();
+        ui.label(bio);
+    }
+    if let Ok(History(history)) = entry.get_component::<History>() {
+        ui.separator();
+        for (age, event) in history {
+            ui.label(format!("{} лет: {}", age.years(), event));
+        }
+    }
 }
 
 /// На каком экране мы сейчас
 pub enum ScreenId {
-    ScreenResources,
-    ScreenDemography,
-    ScreenSpace,
-    ScreenTasks,
+    Resources,
+    Demography,
+    Space,
+    Tasks,
+    Party,
+    Science,
+}
+
+/// Стейт интерфейса ресурсов.
+#[derive(Default)]
+pub struct ResourcesScreenState {
+    pub shipment: HashMap<Resource, usize>, // сколько отгрузить в Главблок
 }
 
 /// Стейт интерфейса пространства.
@@ -171,6 +273,59 @@ impl Default for SpaceScreenState {
     }
 }
 
+/// Стейт интерфейса производства.
+pub struct TasksScreenState {
+    pub order_threshold: usize, // Сколько держать на складе для новых постоянных заказов
+}
+
+impl Default for TasksScreenState {
+    fn default() -> Self {
+        Self {
+            order_threshold: 200,
+        }
+    }
+}
+
+/// Как сортировать список людей
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RosterSort {
+    Name,
+    Age,
+    Mood,
+    Satiety,
+}
+
+impl fmt::Display for RosterSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RosterSort::Name    => write!(f, "по имени"),
+            RosterSort::Age     => write!(f, "по возрасту"),
+            RosterSort::Mood    => write!(f, "по настроению"),
+            RosterSort::Satiety => write!(f, "по сытости"),
+        }
+    }
+}
+
+/// Стейт интерфейса демографии.
+pub struct DemographyScreenState {
+    pub filter_prof: Option<Profession>,
+    pub filter_tier: Option<Tier>,
+    pub filter_status: Option<ComradStatus>,
+    pub sort: RosterSort,
+    pub selected_comrad: Option<Entity>,
+}
+
+impl Default for DemographyScreenState {
+    fn default() -> Self {
+        Self {
+            filter_prof: None,
+            filter_tier: None,
+            filter_status: None,
+            sort: RosterSort::Name,
+            selected_comrad: None,
+        }
+    }
+}
 
 #[cfg_attr(feature = "persistence", derive(serde::Deserialize, serde::Serialize))]
 pub struct GlavblockApp {
@@ -180,7 +335,10 @@ pub struct GlavblockApp {
     pub textures: HashMap<String, TextureId>,
     pub resource_loaders: HashMap<String, mpsc::Receiver<Vec<u8>>>,
     pub current_screen: ScreenId,
+    pub resources_screen: ResourcesScreenState,
     pub space_screen: SpaceScreenState,
+    pub tasks_screen: TasksScreenState,
+    pub demography_screen: DemographyScreenState,
 }
 
 impl Default for GlavblockApp {
@@ -197,9 +355,31 @@ impl Default for GlavblockApp {
         ));
         let resource_loaders = HashMap::new ();
         let textures = HashMap::new ();
-        let current_screen = ScreenId::ScreenResources;
+        let current_screen = ScreenId::Resources;
+        let resources_screen = ResourcesScreenState::default();
         let space_screen = SpaceScreenState::default();
+        let tasks_screen = TasksScreenState::default();
+        let demography_screen = DemographyScreenState::default();
         resources.insert(BuildPowerPool::new());
+        resources.insert(LaborStats::default());
+        resources.insert(RationPolicy::Full);
+        resources.insert(RecentDeaths::default());
+        resources.insert(SamosborFear::default());
+        resources.insert(Unrest::default());
+        resources.insert(UnrestEvents::default());
+        resources.insert(TurnReport::default());
+        resources.insert(PendingRefugees::default());
+        resources.insert(RefugeeRecord::default());
+        resources.insert(PartyTrust::default());
+        resources.insert(Directives::default());
+        resources.insert(Quota::default());
+        resources.insert(Inspections::default());
+        resources.insert(Shrinkage::default());
+        resources.insert(PayScale::default());
+        resources.insert(CouponFund::default());
+        resources.insert(load_tech_tree());
+        resources.insert(Researched::default());
+        resources.insert(NextNeighbourSamosbor::default());
         init_colony(&mut world);
         Self {
             // Example stuff:
@@ -209,7 +389,10 @@ impl Default for GlavblockApp {
             textures,
             resource_loaders,
             current_screen,
+            resources_screen,
             space_screen,
+            tasks_screen,
+            demography_screen,
         }
     }
 }
@@ -244,7 +427,7 @@ impl GlavblockApp {
                 )).on_hover_text("Ресурсы")
                 .clicked()
             {
-                self.current_screen = ScreenId::ScreenResources;
+                self.current_screen = ScreenId::Resources;
             }
             if ui
                 .add(ImageButton::new(
@@ -256,7 +439,7 @@ impl GlavblockApp {
                 )).on_hover_text("Демография")
                 .clicked()
             {
-                self.current_screen = ScreenId::ScreenDemography;
+                self.current_screen = ScreenId::Demography;
             }
             if ui
                 .add(ImageButton::new(
@@ -268,7 +451,7 @@ impl GlavblockApp {
                 )).on_hover_text("Пространство")
                 .clicked()
             {
-                self.current_screen = ScreenId::ScreenSpace;
+                self.current_screen = ScreenId::Space;
             }
             if ui
                 .add(ImageButton::new(
@@ -281,32 +464,426 @@ impl GlavblockApp {
                 .on_hover_text("Производство")
                 .clicked()
             {
-                self.current_screen = ScreenId::ScreenTasks;
+                self.current_screen = ScreenId::Tasks;
+            }
+            if ui
+                .add(ImageButton::new(
+                    get_texture_id(
+                        &mut self.textures,
+                        "assets/party.png".to_string(),
+                    ),
+                    button_txtr_size,
+                ))
+                .on_hover_text("Партия")
+                .clicked()
+            {
+                self.current_screen = ScreenId::Party;
+            }
+            if ui
+                .add(ImageButton::new(
+                    get_texture_id(
+                        &mut self.textures,
+                        "assets/science.png".to_string(),
+                    ),
+                    button_txtr_size,
+                ))
+                .on_hover_text("Наука")
+                .clicked()
+            {
+                self.current_screen = ScreenId::Science;
             }
         });
+        if let Some(game_over) = self.resources.get::<GameOver>().map(|g| g.clone()) {
+            CentralPanel::default().show(ctx, |ui| {
+                ui.heading("Игра окончена");
+                ui.label(game_over.0);
+            });
+            return;
+        }
         match self.current_screen {
-            ScreenId::ScreenResources =>
+            ScreenId::Resources =>
                 self.resources_screen(ctx),
-            ScreenId::ScreenDemography =>
+            ScreenId::Demography =>
                 self.demography_screen(ctx),
-            ScreenId::ScreenSpace =>
+            ScreenId::Space =>
                 self.space_screen(ctx),
-            ScreenId::ScreenTasks =>
+            ScreenId::Tasks =>
                 self.tasks_screen(ctx),
+            ScreenId::Party =>
+                self.party_screen(ctx),
+            ScreenId::Science =>
+                self.science_screen(ctx),
         }
     }
 
+    fn science_screen(
+        &mut self,
+        ctx: &CtxRef,
+    ) {
+        let tree = self.resources
+            .get::<TechTree>()
+            .map(|t| t.clone())
+            .unwrap_or_default();
+        let researched = self.resources
+            .get::<Researched>()
+            .map(|r| r.clone())
+            .unwrap_or_default();
+        let next_samosbor = self.resources
+            .get::<NextNeighbourSamosbor>()
+            .map(|n| *n)
+            .unwrap_or_default();
+        let in_progress = research_in_progress(&self.world);
+        let exists_rsrs = what_we_have(&mut self.world);
+        CentralPanel::default().show(ctx, |ui| {
+            if is_unlocked(&tree, &researched, Unlock::SamosborForecast) {
+                ui.label(format!(
+                    "Прогноз: самосбор у соседей через {} смен",
+                    next_samosbor.0,
+                ));
+            }
+            ui.label(format!(
+                "Реактивы: {} {}, {} {}, {} {}",
+                Resource::ReagentT1,
+                exists_rsrs.get(&Resource::ReagentT1).map(|r| r.0).unwrap_or(0),
+                Resource::ReagentT2,
+                exists_rsrs.get(&Resource::ReagentT2).map(|r| r.0).unwrap_or(0),
+                Resource::ReagentT3,
+                exists_rsrs.get(&Resource::ReagentT3).map(|r| r.0).unwrap_or(0),
+            ));
+            ui.separator();
+            let mut start = None;
+            ScrollArea::auto_sized().show(ui, |ui| {
+                for tech in tree.0.iter() {
+                    CollapsingHeader::new(&tech.name)
+                        .id_source(&tech.id)
+                        .default_open (true)
+                        .show(
+                            ui,
+                            |ui| {
+                                ui.label(&tech.description);
+                                if !tech.requires.is_empty() {
+                                    let requires: Vec<String> = tech.requires
+                                        .iter()
+                                        .map(|id| tree.get(id).map(|t| t.name.clone()).unwrap_or_else(|| id.clone()))
+                                        .collect();
+                                    ui.label(format!("Требует: {}", requires.join(", ")));
+                                }
+                                for req in research_requirements(tech).iter().map(display_task_meta) {
+                                    ui.label(req);
+                                }
+                                for (res, cnt) in research_cost(tech).iter() {
+                                    ui.label(format!("Расход: {}, {}", res, cnt.0));
+                                }
+                                for unlock in tech.unlocks.iter() {
+                                    ui.label(format!("Открывает: {}", unlock));
+                                }
+                                if researched.0.contains(&tech.id) {
+                                    ui.label("Исследовано");
+                                } else if let Some(progress) = in_progress.get(&tech.id) {
+                                    match progress {
+                                        Some(progress) => ui.label(format!("Идет исследование: {}", progress)),
+                                        None => ui.label("Идет исследование"),
+                                    };
+                                } else if can_start_research(&self.world, &tree, &researched, &tech.id) {
+                                    if ui.button("Начать").clicked() {
+                                        start = Some(tech.id.clone());
+                                    }
+                                } else {
+                                    ui.label("Недоступно");
+                                }
+                            }
+                        );
+                }
+            });
+            if let Some(id) = start {
+                let _ = start_research(&mut self.world, &self.resources, &id);
+            }
+            ui.separator();
+            if ui.button("Смена").clicked() {
+                turn(&mut self.world, &mut self.resources);
+            }
+        });
+    }
+
+    fn party_screen(
+        &mut self,
+        ctx: &CtxRef,
+    ) {
+        let mut scale = self.resources
+            .get::<PayScale>()
+            .map(|s| s.clone())
+            .unwrap_or_default();
+        let paid = paid_by_profession(&self.world);
+        let fund = self.resources
+            .get::<CouponFund>()
+            .map(|f| *f)
+            .unwrap_or_default();
+        CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!(
+                "Зарплата за смену: {} талонов, в кассе: {}, на руках у населения: {}",
+                payroll(&self.world, &scale),
+                fund.0,
+                coupons_in_circulation(&self.world),
+            ));
+            ui.label(format!(
+                "Касса пополняется выработкой: талон за {} трудочасов",
+                BP_PER_COUPON,
+            ));
+            ui.label(format!(
+                "Цены: доп. паек {}, нейротерминал {}, вещь из полимера {}",
+                FOOD_PRICE,
+                LEISURE_PRICE,
+                GOODS_PRICE,
+            ));
+            CollapsingHeader::new("Ставки оплаты")
+                .default_open (true)
+                .show(
+                    ui,
+                    |ui| {
+                        for prof in all_professions() {
+                            for tier in [Tier::T1, Tier::T2, Tier::T3].iter() {
+                                ui.horizontal(|ui| {
+                                    ui.label(format!(
+                                        "{} {} ({} чел, ждут {}):",
+                                        prof,
+                                        tier,
+                                        paid.get(&(prof, *tier)).unwrap_or(&0),
+                                        expected_wage(prof, *tier),
+                                    ));
+                                    let wage = scale.0.entry((prof, *tier)).or_insert(0);
+                                    ui.add(DragValue::new(wage).clamp_range(0..=100));
+                                });
+                            }
+                        }
+                    }
+                );
+            self.resources.insert(scale);
+            ui.separator();
+            if ui.button("Смена").clicked() {
+                turn(&mut self.world, &mut self.resources);
+            }
+        });
+    }
+
     fn resources_screen(
         &mut self,
         ctx: &CtxRef,
     ) {
-        let resources = what_we_have(&mut self.world);
+        // начальство видит склады по учету, о пропажах узнает только при пересчете
+        let shrinkage = self.resources
+            .get::<Shrinkage>()
+            .map(|s| s.clone())
+            .unwrap_or_default();
+        let resources = recorded_stock(&mut self.world, &shrinkage);
+        let audit = self.resources
+            .get::<AuditResult>()
+            .map(|a| a.clone());
+        let audit_progress = audit_in_progress(&self.world);
+        let demand = concentrat_demand(&mut self.world);
+        let eaters = eaters(&mut self.world);
+        let turn_report = self.resources
+            .get::<TurnReport>()
+            .map(|r| r.clone())
+            .unwrap_or_default();
+        let trust = self.resources
+            .get::<PartyTrust>()
+            .map(|t| *t)
+            .unwrap_or_default();
+        let directives = self.resources
+            .get::<Directives>()
+            .map(|d| d.clone())
+            .unwrap_or_default();
+        let quota = self.resources
+            .get::<Quota>()
+            .map(|q| q.clone())
+            .unwrap_or_default();
+        let inspections = self.resources
+            .get::<Inspections>()
+            .map(|i| i.clone())
+            .unwrap_or_default();
         CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!("Доверие партии: {}/{}", trust.0, TRUST_MAX));
+            CollapsingHeader::new("Директивы Главблока")
+                .default_open (true)
+                .show(
+                    ui,
+                    |ui| {
+                        if directives.active.is_empty() {
+                            ui.label(format!(
+                                "Директив нет, следующая через {} смен",
+                                directives.next_in,
+                            ));
+                        }
+                        for (i, directive) in directives.active.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label(format!(
+                                    "{}, осталось {} смен (+{}/-{})",
+                                    directive.goal,
+                                    directive.shifts_left,
+                                    directive.reward,
+                                    directive.penalty,
+                                ));
+                                if let DirectiveGoal::Deliver(_, _) = directive.goal {
+                                    if ui.button("Сдать").clicked() {
+                                        let _ = fulfil_directive(
+                                            &mut self.world,
+                                            &mut self.resources,
+                                            i,
+                                        );
+                                    }
+                                }
+                            });
+                        }
+                        if trust.0 >= TRUST_SUPPLY {
+                            ui.label(format!(
+                                "Снабжение через {} смен",
+                                directives.supply_in,
+                            ));
+                        }
+                    }
+                );
+            CollapsingHeader::new("План поставок в Главблок")
+                .default_open (true)
+                .show(
+                    ui,
+                    |ui| {
+                        ui.label(format!("До конца периода {} смен", quota.shifts_left));
+                        let mut planned: Vec<Resource> = quota.demand
+                            .keys()
+                            .chain(quota.shipped.keys())
+                            .cloned()
+                            .collect();
+                        planned.sort();
+                        planned.dedup();
+                        for res in planned {
+                            let demand = quota.demand.get(&res).map(|a| a.0).unwrap_or(0);
+                            let shipped = quota.shipped.get(&res).map(|a| a.0).unwrap_or(0);
+                            let have = resources.get(&res).map(|a| a.0).unwrap_or(0);
+                            ui.horizontal(|ui| {
+                                ui.label(format!(
+                                    "{}: отгружено {} из {}, на складе {}",
+                                    res,
+                                    shipped,
+                                    demand,
+                                    have,
+                                ));
+                                let amount = self.resources_screen.shipment
+                                    .entry(res)
+                                    .or_insert(0);
+                                ui.add(DragValue::new(amount).clamp_range(0..=have));
+                            });
+                        }
+                        if ui.button("Отгрузить").clicked() {
+                            let bunch = self.resources_screen.shipment
+                                .drain()
+                                .map(|(res, amount)| (res, RealUnits(amount)))
+                                .collect();
+                            let _ = ship_quota(
+                                &mut self.world,
+                                &mut self.resources,
+                                bunch,
+                            );
+                        }
+                    }
+                );
+            CollapsingHeader::new("Партийная комиссия")
+                .default_open (false)
+                .show(
+                    ui,
+                    |ui| {
+                        ui.label(format!(
+                            "Функционеров: {}, следующая проверка через {} смен",
+                            functionaries(&self.world).len(),
+                            inspections.next_in,
+                        ));
+                        ui.horizontal(|ui| {
+                            ui.label(format!(
+                                "Кладовщиков: {} из {} положенных",
+                                storekeepers(&self.world).len(),
+                                storekeepers_required(stored_volume(&self.world)),
+                            ));
+                            if ui.button("Назначить").clicked() {
+                                let _ = assign_storekeeper(&mut self.world);
+                            }
+                            if ui.button("Снять").clicked() {
+                                dismiss_storekeeper(&mut self.world);
+                            }
+                        });
+                        let act = match inspections.last.as_ref() {
+                            Some(act) => act,
+                            None => {
+                                ui.label("Проверок еще не было");
+                                return;
+                            },
+                        };
+                        for finding in act.findings.iter() {
+                            ui.label(format!(
+                                "{}: {}. {}",
+                                finding.area,
+                                if finding.passed { "в порядке" } else { "нарушение" },
+                                finding.note,
+                            ));
+                        }
+                        for (res, amount) in act.fines.iter() {
+                            ui.label(format!("Изъято {}: {}", res, amount.0));
+                        }
+                        for name in act.promoted.iter() {
+                            ui.label(format!("Повышен: {}", name));
+                        }
+                        for name in act.arrested.iter() {
+                            ui.label(format!("Арестован: {}", name));
+                        }
+                        ui.label(format!("Доверие партии: {:+}", act.trust));
+                    }
+                );
+            CollapsingHeader::new("Сводка за смену")
+                .default_open (true)
+                .show(
+                    ui,
+                    |ui| {
+                        if turn_report.0.is_empty() {
+                            ui.label("Ничего важного не случилось");
+                        }
+                        for event in turn_report.0.iter() {
+                            ui.label(event);
+                        }
+                    }
+                );
+            let stock = resources
+                .get(&Resource::Concentrat)
+                .unwrap_or(&RealUnits(0))
+                .0;
+            ui.label(format!(
+                "Едоков: {}, запаса концентрата на {} смен",
+                demand,
+                stock.checked_div(demand).unwrap_or(0),
+            ));
+            CollapsingHeader::new("Пайки")
+                .default_open (false)
+                .show(
+                    ui,
+                    |ui| {
+                        let mut policy = self.resources
+                            .get::<RationPolicy>()
+                            .map(|p| *p)
+                            .unwrap_or(RationPolicy::Full);
+                        for p in all_ration_policies() {
+                            let days = predict_days_of_food(&eaters, stock, p);
+                            ui.radio_value(
+                                &mut policy,
+                                p,
+                                format!("{} - концентрата хватит на {} смен", p, days),
+                            );
+                        }
+                        self.resources.insert(policy);
+                    }
+                );
             CollapsingHeader::new("Ресурсы")
                 .default_open (true)
                 .show(
                     ui,
                     |ui| {
+                        ui.label("По учету:");
                         for res in all_resources().iter () {
                         let cnt = resources
                                 .get(&res)
@@ -315,8 +892,46 @@ impl GlavblockApp {
                                 &format!("{}: {}", res, cnt.0),
                             );
                         }
+                        match audit_progress.as_ref() {
+                            Some(progress) => {
+                                ui.label(format!(
+                                    "Идет инвентаризация: {}/{}",
+                                    progress.bp_invested.0,
+                                    progress.bp_required.0,
+                                ));
+                            },
+                            None => {
+                                if ui.button("Провести инвентаризацию").clicked() {
+                                    order_audit(&mut self.world);
+                                }
+                            },
+                        }
                     }
                 );
+            if let Some(audit) = audit.as_ref() {
+                CollapsingHeader::new("Итоги инвентаризации")
+                    .default_open (false)
+                    .show(
+                        ui,
+                        |ui| {
+                            let shortage = audit.shortage();
+                            for res in all_resources().iter () {
+                                let recorded = audit.recorded.get(res).map(|a| a.0).unwrap_or(0);
+                                let counted = audit.counted.get(res).map(|a| a.0).unwrap_or(0);
+                                if recorded == 0 && counted == 0 {
+                                    continue;
+                                }
+                                ui.label(format!(
+                                    "{}: по учету {}, пересчитано {}, недостача {}",
+                                    res,
+                                    recorded,
+                                    counted,
+                                    shortage.get(res).map(|a| a.0).unwrap_or(0),
+                                ));
+                            }
+                        }
+                    );
+            }
             ui.separator ();
             if ui.button("Смена").clicked() {
                 turn(&mut self.world, &mut self.resources);
@@ -328,7 +943,31 @@ impl GlavblockApp {
         &mut self,
         ctx: &CtxRef,
     ) {
+        let labor = self.resources
+            .get::<LaborStats>()
+            .map(|l| *l)
+            .unwrap_or_default();
+        let tree = self.resources
+            .get::<TechTree>()
+            .map(|t| t.clone())
+            .unwrap_or_default();
+        let researched = self.resources
+            .get::<Researched>()
+            .map(|r| r.clone())
+            .unwrap_or_default();
         CentralPanel::default().show(ctx, |ui| {
+            ui.label(format!(
+                "Выработка за смену: {} из {}, потеряно всего: {}, из них из-за морали: {}, отказались работать: {}, бастуют: {}, болеют: {}, учатся: {}",
+                labor.actual.0,
+                labor.potential.0,
+                labor.lost().0,
+                labor.morale_lost.0,
+                labor.refused,
+                labor.striking,
+                labor.sick,
+                labor.training,
+            ));
+            ui.separator();
             ui.columns(2, | cols| {
                 // отрисовать CollapsedHeader-ами все доступные стационарки, гермы, ресурсы, изделия
                 // недоступные к постройке отрисовывать красным
@@ -342,6 +981,8 @@ impl GlavblockApp {
                             for stat in all_stationaries().iter() {
                                 let stat_meta = can_build_stationary (
                                     &mut self.world,
+                                    &tree,
+                                    &researched,
                                     exists_rsrs.clone(), //FIXME
                                     *stat,
                                 );
@@ -362,28 +1003,25 @@ impl GlavblockApp {
                                             TaskPriority (0),
                                         );
                                     },
-                                    Err((
-                                        not_enough_stts,
-                                        not_enough_ppl,
-                                        not_enough_rsrcs,
-                                        is_enough_space
-                                    )) => {
+                                    Err(shortage) => {
                                         ui.add(
                                             Button::new(&format!("{}", *stat)).text_color(Color32::RED)
                                         ).on_hover_ui(
                                             |ui| {
                                                 ui.label("Не хватает:");
-                                                for v in not_enough_stts.iter() {
+                                                for v in shortage.stationaries.iter() {
                                                     ui.label(format!("{}", *v));
                                                 }
-                                                for v in not_enough_ppl.iter() {
-                                                    let v_ = *v;
-                                                    ui.label(format!("{}, {}", v_.0, v_.1));
+                                                for v in shortage.people.iter() {
+                                                    ui.label(format!("{}, {}", v.0, v.1));
                                                 }
-                                                for v in not_enough_rsrcs.iter() {
+                                                for v in shortage.resources.iter() {
                                                     ui.label(format!("{}, {}", v.0, v.1.0));
                                                 }
-                                                if !is_enough_space {
+                                                for v in shortage.research.iter() {
+                                                    ui.label(format!("Исследование: {}", v));
+                                                }
+                                                if shortage.no_room {
                                                     ui.label("А еще места нет");
                                                 }
                                             }
@@ -394,6 +1032,174 @@ impl GlavblockApp {
                             }
                         }
                     );
+                CollapsingHeader::new("Ставить гермы")
+                    .default_open (false)
+                    .show(
+                        &mut cols[0],
+                        |ui| {
+                            for germ in all_germs() {
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("{}", germ)).on_hover_ui(
+                                        |ui| {
+                                            for req in germ_requirements(germ).iter().map(display_task_meta) {
+                                                ui.label(req);
+                                            }
+                                        }
+                                    );
+                                    match can_install_germ(&mut self.world, &tree, &researched, germ) {
+                                        Ok(()) => for purpose in germ_purposes(germ) {
+                                            if ui.button(purpose.to_string()).clicked() {
+                                                install_germ(&mut self.world, germ, purpose);
+                                            }
+                                        },
+                                        Err(shortage) => for purpose in germ_purposes(germ) {
+                                            ui.add(
+                                                Button::new(purpose.to_string()).text_color(Color32::RED)
+                                            ).on_hover_ui(
+                                                |ui| {
+                                                    ui.label("Не хватает:");
+                                                    for v in shortage.stationaries.iter() {
+                                                        ui.label(format!("{}", *v));
+                                                    }
+                                                    for v in shortage.people.iter() {
+                                                        ui.label(format!("{}, {}", v.0, v.1));
+                                                    }
+                                                    for v in shortage.research.iter() {
+                                                        ui.label(format!("Исследование: {}", v));
+                                                    }
+                                                }
+                                            );
+                                        },
+                                    }
+                                });
+                            }
+                        }
+                    );
+                CollapsingHeader::new("Производить")
+                    .default_open (false)
+                    .show(
+                        &mut cols[0],
+                        |ui| {
+                            let exists_rsrs = what_we_have(&mut self.world);
+                            for recipe in all_recipes().iter() {
+                                if !is_unlocked(&tree, &researched, Unlock::Recipe(*recipe)) {
+                                    continue;
+                                }
+                                ui.horizontal(|ui| {
+                                    ui.label(format!("{}", *recipe)).on_hover_ui(
+                                        |ui| {
+                                            for req in recipe_requirements(*recipe).iter().map(display_task_meta) {
+                                                ui.label(req);
+                                            }
+                                            for (res, cnt) in recipe_inputs(*recipe).iter() {
+                                                ui.label(format!("Расход: {}, {}", res, cnt.0));
+                                            }
+                                            for (res, cnt) in recipe_outputs(*recipe).iter() {
+                                                ui.label(format!("Выход: {}, {}", res, cnt.0));
+                                            }
+                                            ui.label(format!("Смен: {}", recipe_duration(*recipe)));
+                                        }
+                                    );
+                                    for amount in [1, 10].iter() {
+                                        let title = format!("x{}", amount);
+                                        match can_order_batch(
+                                            &mut self.world,
+                                            &tree,
+                                            &researched,
+                                            exists_rsrs.clone(),
+                                            *recipe,
+                                            *amount,
+                                        ) {
+                                            Ok(()) => if ui.button(&title).clicked() {
+                                                let _ = order_batch(&mut self.world, &self.resources, *recipe, *amount);
+                                            },
+                                            Err(shortage) => {
+                                                ui.add(
+                                                    Button::new(&title).text_color(Color32::RED)
+                                                ).on_hover_ui(
+                                                    |ui| {
+                                                        ui.label("Не хватает:");
+                                                        for v in shortage.stationaries.iter() {
+                                                            ui.label(format!("{}", *v));
+                                                        }
+                                                        for v in shortage.people.iter() {
+                                                            ui.label(format!("{}, {}", v.0, v.1));
+                                                        }
+                                                        for v in shortage.resources.iter() {
+                                                            ui.label(format!("{}, {}", v.0, v.1.0));
+                                                        }
+                                                        for v in shortage.research.iter() {
+                                                            ui.label(format!("Исследование: {}", v));
+                                                        }
+                                                    }
+                                                );
+                                            }
+                                        }
+                                    }
+                                });
+                            }
+                        }
+                    );
+                CollapsingHeader::new("Изготовить")
+                    .default_open (false)
+                    .show(
+                        &mut cols[0],
+                        |ui| {
+                            let exists_rsrs = what_we_have(&mut self.world);
+                            for item in all_items().iter() {
+                                for tier in [Tier::T1, Tier::T2, Tier::T3].iter() {
+                                    let title = format!("{} {}", *item, *tier);
+                                    match can_make_item(
+                                        &mut self.world,
+                                        exists_rsrs.clone(),
+                                        *item,
+                                        *tier,
+                                    ) {
+                                        Ok(room) => if ui.add(
+                                            Button::new(&title)
+                                        ).on_hover_ui(
+                                            |ui| {
+                                                for req in item_requirements(*item, *tier).iter().map(display_task_meta) {
+                                                    ui.label(req);
+                                                }
+                                                for (res, cnt) in item_required_resources(*item, *tier).iter() {
+                                                    ui.label(format!("{}, {}", res, cnt.0));
+                                                }
+                                            }
+                                        ).clicked () {
+                                            let _ = start_item_task(
+                                                &mut self.world,
+                                                *item,
+                                                *tier,
+                                                room,
+                                            );
+                                        },
+                                        Err(shortage) => {
+                                            ui.add(
+                                                Button::new(&title).text_color(Color32::RED)
+                                            ).on_hover_ui(
+                                                |ui| {
+                                                    ui.label("Не хватает:");
+                                                    for v in shortage.stationaries.iter() {
+                                                        ui.label(format!("{}", *v));
+                                                    }
+                                                    for v in shortage.people.iter() {
+                                                        ui.label(format!("{}, {}", v.0, v.1));
+                                                    }
+                                                    for v in shortage.resources.iter() {
+                                                        ui.label(format!("{}, {}", v.0, v.1.0));
+                                                    }
+                                                    if shortage.no_room {
+                                                        ui.label("А еще места на складе нет");
+                                                    }
+                                                }
+                                            );
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    );
                 let mut in_progress:Vec<(Stationary, TaskProgress)>  = currently_building(&mut self.world)
                     .iter()
                     .cloned()
@@ -404,8 +1210,88 @@ impl GlavblockApp {
                 for ip in in_progress.iter () {
                     right.label(format!("{} , {}", ip.0, ip.1));
                 }
+                for (batch, progress, shifts_left) in batches_in_progress(&self.world).iter() {
+                    right.label(format!(
+                        "{} x{} , {}, осталось смен: {}",
+                        batch.recipe, batch.amount, progress, shifts_left,
+                    ));
+                }
+                for (item, tier, progress) in items_in_progress(&self.world).iter() {
+                    right.label(format!("{} {} , {}", item, tier, progress));
+                }
+                right.separator();
+                let mut stock: Vec<String> = items_in_stock(&self.world)
+                    .iter()
+                    .map(|((item, tier), cnt)| format!("{} {} на складе: {}", item, tier, cnt))
+                    .collect();
+                stock.sort();
+                for row in stock {
+                    right.label(row);
+                }
             });
 
+            ui.separator();
+            CollapsingHeader::new("Постоянные заказы")
+                .default_open (false)
+                .show(
+                    ui,
+                    |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Держать на складе не меньше:");
+                            ui.add(DragValue::new(&mut self.tasks_screen.order_threshold));
+                        });
+                        let orders = standing_orders(&self.world);
+                        for (stat_entity, stat) in ready_stationaries(&self.world) {
+                            let recipes: Vec<Recipe> = all_recipes()
+                                .into_iter()
+                                .filter(|r| recipe_stationary(*r) == stat)
+                                .filter(|r| is_unlocked(&tree, &researched, Unlock::Recipe(*r)))
+                                .collect();
+                            if recipes.is_empty() {
+                                continue;
+                            }
+                            ui.label(format!("{}", stat));
+                            ui.indent(stat_entity, |ui| {
+                                for (order_entity, _, order) in orders
+                                    .iter()
+                                    .filter(|(_, s, _)| *s == stat_entity)
+                                {
+                                    ui.horizontal(|ui| {
+                                        ui.label(format!("{}: {}", order.recipe, order.kind));
+                                        let mut active = !order.paused;
+                                        if ui.checkbox(&mut active, "работает").changed() {
+                                            toggle_standing_order(&mut self.world, *order_entity);
+                                        }
+                                        if ui.button("Снять").clicked() {
+                                            remove_standing_order(&mut self.world, *order_entity);
+                                        }
+                                    });
+                                }
+                                for recipe in recipes {
+                                    ui.horizontal(|ui| {
+                                        ui.label(format!("{}", recipe));
+                                        if ui.button("Держать запас").clicked() {
+                                            add_standing_order(
+                                                &mut self.world,
+                                                stat_entity,
+                                                recipe,
+                                                OrderKind::KeepInStock(RealUnits(self.tasks_screen.order_threshold)),
+                                            );
+                                        }
+                                        if ui.button("Непрерывно").clicked() {
+                                            add_standing_order(
+                                                &mut self.world,
+                                                stat_entity,
+                                                recipe,
+                                                OrderKind::Continuous,
+                                            );
+                                        }
+                                    });
+                                }
+                            });
+                        }
+                    }
+                );
             ui.separator();
             if ui.button("Смена").clicked() {
                 turn(&mut self.world, &mut self.resources);
@@ -494,6 +1380,8 @@ impl GlavblockApp {
             let stationaries = what_take_place(
                 &mut self.world,
             );
+            let sick = sick_by_room(&self.world);
+            let mut to_rehouse = None;
             ui.columns(
                 2,
                 |cols| {
@@ -530,12 +1418,19 @@ impl GlavblockApp {
                                 .filter(
                                     |(entity, (atype, _, _, _) )| include_purposes.contains(atype)
                                 ) {
-                                    result.push((
-                                        *(room.0),
-                                        format!(
-                                            "{}, вместимость: {} кв.м., свободно: {} кв.м.",
-                                            room.1.0, room.1.1.0 / 100, room.1.2.0 / 100
-                                        )));
+                                    let mut row = format!(
+                                        "{}, вместимость: {} кв.м., свободно: {} кв.м.",
+                                        room.1.0, room.1.1.0 / 100, room.1.2.0 / 100
+                                    );
+                                    if room.1.0 == AreaType::Living {
+                                        row = format!(
+                                            "{}, заполнена на {}%, больных: {}",
+                                            row,
+                                            (crowding(room.1.3, room.1.1) * 100.0) as usize,
+                                            sick.get(room.0).unwrap_or(&0),
+                                        );
+                                    }
+                                    result.push((*(room.0), row));
                                 };
                             result.sort_by(|(_, a), (_, b)|(*a).cmp(b));
                             for row in result {
@@ -551,12 +1446,27 @@ impl GlavblockApp {
                         let people_in_room = people
                             .get(&entity)
                             .unwrap_or(&empty);
-                        for human in people_in_room {
-                            room_contains.push(format!(
-                                "{} {}, занимает {} м.кв.",
-                                human.0, human.1, human.2.0 / 100
+                        let mut humans: Vec<(String, Entity)> = people_in_room
+                            .iter()
+                            .map(|human| (
+                                format!(
+                                    "{} {}, занимает {} м.кв.",
+                                    human.1, human.2, human.3.0 / 100
+                                ),
+                                human.0,
                             ))
-                        };
+                            .collect();
+                        humans.sort_by(|a, b| a.0.cmp(&b.0));
+                        let world = &self.world;
+                        for (row, human) in humans {
+                            cols[1].horizontal(|ui| {
+                                ui.label(row)
+                                    .on_hover_ui(|ui| mood_tooltip(ui, world, human));
+                                if ui.button("Переселить").clicked() {
+                                    to_rehouse = Some(human);
+                                }
+                            });
+                        }
                         let empty2 = Vec::new();
                         let stationaries_in_room = stationaries
                             .get(&entity)
@@ -575,10 +1485,18 @@ impl GlavblockApp {
                     };
                 }
             );
+            if let Some(human) = to_rehouse {
+                let _ = rehouse_comrad(&mut self.world, human);
+            }
             ui.separator ();
-            if ui.button("Смена").clicked() {
-                turn(&mut self.world, &mut self.resources);
-            };
+            ui.horizontal(|ui| {
+                if ui.button("Расселить тесные жилячейки").clicked() {
+                    rehouse_crowded(&mut self.world);
+                }
+                if ui.button("Смена").clicked() {
+                    turn(&mut self.world, &mut self.resources);
+                };
+            });
         });
     }
 
@@ -588,19 +1506,279 @@ impl GlavblockApp {
         ctx: &CtxRef,
     ) {
         let people = people_by_profession(&mut self.world);
+        let squads = all_squads(&self.world);
+        let mut unrest = self.resources
+            .get::<Unrest>()
+            .map(|u| *u)
+            .unwrap_or_default();
+        let events = self.resources
+            .get::<UnrestEvents>()
+            .map(|e| e.clone())
+            .unwrap_or_default();
+        let suppressors = suppressors_count(&self.world);
+        let strikers = strikers_count(&self.world);
         CentralPanel::default().show(ctx, |ui| {
-            let mut rows: Vec<String> = Vec::new();
-            for ((prof, tier), count) in people {
-                rows.push(format!("{} {} - {} чел", prof, tier, count));
-            };
+            ui.label(format!(
+                "Волнения: {} из {}, смен подряд с плохим настроением: {}, сейчас бастует: {} чел",
+                unrest.level, UNREST_MAX, unrest.low_mood_shifts, strikers,
+            ));
+            if unrest.level > 0 {
+                let mut looted: Vec<String> = events.looted
+                    .iter()
+                    .map(|(res, cnt)| format!("{} {}", res, cnt.0))
+                    .collect();
+                looted.sort();
+                ui.label(format!(
+                    "За смену: бастует {} чел, сбежало {} чел, разграблено: {}",
+                    events.striking,
+                    events.deserted,
+                    if looted.is_empty() { "ничего".to_string() } else { looted.join(", ") },
+                ));
+            }
+            ui.checkbox(
+                &mut unrest.suppress,
+                format!("Подавлять волнения силами партии и ликвидаторов ({} чел)", suppressors),
+            );
+            self.resources.insert(unrest);
+            ui.separator();
+            let pending = self.resources
+                .get::<PendingRefugees>()
+                .map(|p| p.clone())
+                .unwrap_or_default();
+            if !pending.0.is_empty() {
+                let mut decision = None;
+                CollapsingHeader::new("Беженцы у гермы")
+                    .default_open (true)
+                    .show(
+                        ui,
+                        |ui| {
+                            for (i, group) in pending.0.iter().enumerate() {
+                                let mut counts: HashMap<(Profession, Tier), usize> = HashMap::new();
+                                for member in group.members.iter() {
+                                    *counts.entry(*member).or_insert(0) += 1;
+                                }
+                                let mut members: Vec<String> = counts
+                                    .iter()
+                                    .map(|((prof, tier), cnt)| format!("{} {} x{}", prof, tier, cnt))
+                                    .collect();
+                                members.sort();
+                                ui.horizontal(|ui| {
+                                    ui.label(format!(
+                                        "{}: {}. Ждут еще {} смен",
+                                        group.origin, members.join(", "), group.shifts_left,
+                                    ));
+                                    if ui.button("Впустить").clicked() {
+                                        decision = Some((i, true));
+                                    }
+                                    if ui.button("Отказать").clicked() {
+                                        decision = Some((i, false));
+                                    }
+                                });
+                            }
+                        }
+                    );
+                if let Some((i, accept)) = decision {
+                    let mut pending = pending;
+                    let group = pending.0.remove(i);
+                    self.resources.insert(pending);
+                    if accept {
+                        accept_refugees(&mut self.world, &mut self.resources, group);
+                    } else {
+                        refuse_refugees(&mut self.resources, group);
+                    }
+                }
+                ui.separator();
+            }
+            let mut rows: Vec<(Profession, Tier, usize)> = people
+                .into_iter()
+                .map(|((prof, tier), count)| (prof, tier, count))
+                .collect();
             rows.sort();
-            for row in rows {
+            for (prof, tier, count) in rows {
                  if ui.button(
-                    row
+                    format!("{} {} - {} чел", prof, tier, count)
                  ).clicked () {
+                     self.demography_screen.filter_prof = Some(prof);
+                     self.demography_screen.filter_tier = Some(tier);
                  }
             }
             ui.separator();
+            CollapsingHeader::new("Отряды")
+                .default_open (true)
+                .show(
+                    ui,
+                    |ui| {
+                        for (squad, name, standard, mission, members, missing) in squads {
+                            ui.horizontal(|ui| {
+                                let status = if missing.is_empty() {
+                                    "укомплектован".to_string()
+                                } else {
+                                    let mut lacks: Vec<String> = missing
+                                        .iter()
+                                        .map(|(role, cnt)| format!("{} x{}", role, cnt))
+                                        .collect();
+                                    lacks.sort();
+                                    format!("некомплект: {}", lacks.join(", "))
+                                };
+                                ui.label(format!(
+                                    "{} ({}), {} чел, {}. {}. Боевая мощь: {}",
+                                    name, standard, members, status, mission.mission,
+                                    squad_combat_power(&self.world, squad),
+                                ));
+                                if mission.mission == Mission::Idle && missing.is_empty() {
+                                    if ui.button(format!("{}", Mission::Patrol)).clicked() {
+                                        let _ = dispatch_squad(&mut self.world, squad, Mission::Patrol);
+                                    }
+                                    if ui.button(format!("{}", Mission::Cleanup)).clicked() {
+                                        let _ = dispatch_squad(&mut self.world, squad, Mission::Cleanup);
+                                    }
+                                }
+                            });
+                        }
+                        ui.horizontal(|ui| {
+                            ui.label(format!(
+                                "Свободных ликвидаторов: {}. Сформировать:",
+                                free_likvidators(&self.world).len(),
+                            ));
+                            for standard in all_squad_standards() {
+                                if ui.button(format!("{}", standard)).clicked() {
+                                    let _ = form_squad(&mut self.world, standard);
+                                }
+                            }
+                        });
+                    }
+                );
+            ui.separator();
+            let state = &mut self.demography_screen;
+            ui.horizontal(|ui| {
+                ui.label("Профессия:");
+                ui.selectable_value(&mut state.filter_prof, None, "все");
+                for prof in all_professions() {
+                    ui.selectable_value(&mut state.filter_prof, Some(prof), format!("{}", prof));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Разряд:");
+                ui.selectable_value(&mut state.filter_tier, None, "все");
+                for tier in [Tier::NoTier, Tier::T1, Tier::T2, Tier::T3] {
+                    ui.selectable_value(&mut state.filter_tier, Some(tier), format!("{}", tier));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Статус:");
+                ui.selectable_value(&mut state.filter_status, None, "все");
+                for status in all_comrad_statuses() {
+                    ui.selectable_value(&mut state.filter_status, Some(status), format!("{}", status));
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Сортировать:");
+                for sort in [RosterSort::Name, RosterSort::Age, RosterSort::Mood, RosterSort::Satiety] {
+                    ui.selectable_value(&mut state.sort, sort, format!("{}", sort));
+                }
+            });
+            let mut people_list: Vec<RosterEntry> = roster(&self.world)
+                .into_iter()
+                .filter(|c| state.filter_prof.map(|p| p == c.prof).unwrap_or(true))
+                .filter(|c| state.filter_tier.map(|t| t == c.tier).unwrap_or(true))
+                .filter(|c| state.filter_status.map(|s| s == c.status).unwrap_or(true))
+                .collect();
+            match state.sort {
+                RosterSort::Name    => people_list.sort_by(|a, b| a.name.0.cmp(&b.name.0)),
+                RosterSort::Age     => people_list.sort_by_key(|c| c.age),
+                RosterSort::Mood    => people_list.sort_by_key(|c| c.mood.0),
+                RosterSort::Satiety => people_list.sort_by_key(|c| c.satiety.0),
+            }
+            let world = &self.world;
+            let mut to_enroll = None;
+            ui.columns(2, |cols| {
+                ScrollArea::from_max_height(400.0).show(&mut cols[0], |ui| {
+                    for c in people_list.iter() {
+                        let row = format!(
+                            "{}, {} лет, {} {}. {}",
+                            c.name.0, c.age.years(), c.prof, c.tier, c.status,
+                        );
+                        if ui.selectable_label(state.selected_comrad == Some(c.comrad), row).clicked() {
+                            state.selected_comrad = Some(c.comrad);
+                        }
+                    }
+                });
+                if let Some(comrad) = state.selected_comrad {
+                    comrad_card(&mut cols[1], world, comrad);
+                    let current = people_list
+                        .iter()
+                        .find(|c| c.comrad == comrad)
+                        .map(|c| (c.prof, c.status));
+                    if let Some((prof, status)) = current {
+                        if status != ComradStatus::Training {
+                            cols[1].separator();
+                            cols[1].label("Переобучить:");
+                            for target in all_professions().into_iter().filter(|p| *p != prof) {
+                                let button = cols[1]
+                                    .button(format!("{}", target))
+                                    .on_hover_text(format!(
+                                        "{} смен, {} пачек концентрата",
+                                        course_duration(target),
+                                        course_cost(target)
+                                            .get(&Resource::Concentrat)
+                                            .map(|c| c.0)
+                                            .unwrap_or(0),
+                                    ));
+                                if button.clicked() {
+                                    to_enroll = Some((comrad, target));
+                                }
+                            }
+                        }
+                    }
+                }
+            });
+            if let Some((comrad, target)) = to_enroll {
+                let _ = enroll(&mut self.world, comrad, target);
+            }
+            CollapsingHeader::new("Половозрастная пирамида")
+                .default_open (false)
+                .show(
+                    ui,
+                    |ui| {
+                        let pyramid = age_pyramid(&self.world);
+                        let widest = pyramid
+                            .iter()
+                            .map(|(_, m, f)| *m.max(f))
+                            .max()
+                            .unwrap_or(0)
+                            .max(1);
+                        for (from, males, females) in pyramid {
+                            let bar = |count: usize| "█".repeat((count * 20).div_ceil(widest));
+                            ui.monospace(format!(
+                                "{:>3}-{:<3} {:>20}|{:<20} муж. {}, жен. {}",
+                                from, from + 9, bar(males), bar(females), males, females,
+                            ));
+                        }
+                    }
+                );
+            CollapsingHeader::new("Обучение")
+                .default_open (false)
+                .show(
+                    ui,
+                    |ui| {
+                        for (student, target, progress, shifts_left) in courses_in_progress(&self.world) {
+                            let name = self.world
+                                .entry_ref(student)
+                                .ok()
+                                .and_then(|entry| entry.get_component::<Name>().ok().cloned())
+                                .map(|Name(name)| name)
+                                .unwrap_or_default();
+                            let taught = progress
+                                .map(|p| format!("{}/{}", p.bp_invested.0, p.bp_required.0))
+                                .unwrap_or_else(|| "завершено".to_string());
+                            ui.label(format!(
+                                "{}: на {}, преподавание {}, осталось смен: {}",
+                                name, target, taught, shifts_left,
+                            ));
+                        }
+                    }
+                );
+            ui.separator();
             if ui.button("Смена").clicked() {
                 turn(&mut self.world, &mut self.resources);
             };
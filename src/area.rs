@@ -19,11 +19,11 @@ pub enum AreaType {
 impl fmt::Display for AreaType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            AreaType::Living     => write!(f, "{}", "Жилячейка"),
-            AreaType::Science    => write!(f, "{}", "Лаборатория"),
-            AreaType::Military   => write!(f, "{}", "Казармы"),
-            AreaType::Industrial => write!(f, "{}", "Цех"),
-            AreaType::Party      => write!(f, "{}", "Партпомещение"),
+            AreaType::Living     => write!(f, "Жилячейка"),
+            AreaType::Science    => write!(f, "Лаборатория"),
+            AreaType::Military   => write!(f, "Казармы"),
+            AreaType::Industrial => write!(f, "Цех"),
+            AreaType::Party      => write!(f, "Партпомещение"),
         }
     }
 }
@@ -109,7 +109,7 @@ pub fn get_room_free_space(
         let occupied_ = occupied.0;
         sum += occupied_;
     };
-    AreaFree(capacity - sum)
+    AreaFree(capacity.saturating_sub(sum))
 }
 
 /// Есть ли у нас комната этого назначения
@@ -142,7 +142,7 @@ pub fn get_sufficent_room(
     // Собираем заполненность помещений
     for (room, volume) in volumeq.iter(world) {
         match areas.get_mut(&room.0) {
-            Some(free) => free.0 += volume.0,
+            Some(free) => free.0 = free.0.saturating_sub(volume.0),
             None => (),
         }
     };
@@ -206,7 +206,8 @@ pub fn all_rooms_with_space(
         match result.get_mut(&room.0) {
             Some(room_) => {
                 // свободного пространства меньше на величину объекта
-                room_.2.0 -= volume.0;
+                // помещение может быть переполнено людьми
+                room_.2.0 = room_.2.0.saturating_sub(volume.0);
                 // а заполненного соотв больше
                 room_.3.0 += volume.0;
             },
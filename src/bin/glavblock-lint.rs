@@ -0,0 +1,22 @@
+//! Проверка игрового контента.
+//! glavblock-lint [content.ron] [research.ron]
+#![forbid(unsafe_code)]
+#![warn(clippy::all, rust_2018_idioms)]
+
+use std::process;
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let content_path = args.get(1).map(|s| s.as_str()).unwrap_or(glavblock::CONTENT_PATH);
+    let tech_tree_path = args.get(2).map(|s| s.as_str()).unwrap_or(glavblock::TECH_TREE_PATH);
+    let errors = glavblock::lint_files(content_path, tech_tree_path);
+    if errors.is_empty() {
+        println!("Контент в порядке: {}, {}", content_path, tech_tree_path);
+        return;
+    }
+    for error in errors.iter() {
+        eprintln!("{}", error);
+    }
+    eprintln!("Найдено ошибок: {}", errors.len());
+    process::exit(1);
+}
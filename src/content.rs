@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::core::*;
+use crate::people::*;
+use crate::production::*;
+use crate::resources::*;
+
+/// Где лежит файл с контентом
+pub static CONTENT_PATH: &str = "data/content.ron";
+
+/// Контент, вшитый в сборку. Нужен в wasm и если файла рядом нет.
+static CONTENT_EMBEDDED: &str = include_str!("../data/content.ron");
+
+/// Трудозатраты из файла контента
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub struct LaborDef {
+    pub prof: Profession,
+    pub tier: Tier,
+    pub bp: usize,
+    pub stationary: Stationary, // на каком оборудовании надо выполнять работу
+}
+
+impl LaborDef {
+    pub fn task_meta(&self) -> TaskMeta {
+        TaskMeta {
+            prof: self.prof,
+            tier: self.tier,
+            bp: BuildPower(self.bp),
+            stationary: self.stationary,
+        }
+    }
+}
+
+/// Описание стационарки
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct StationaryDef {
+    pub size: usize, // сколько площади занимает
+    pub build_power: usize, // сколько работы дает за смену
+    pub resources: HashMap<Resource, usize>, // из чего строится
+    pub labor: Vec<LaborDef>, // кто и сколько строит
+    pub prerequisites: Vec<Stationary>, // что уже должно стоять
+}
+
+/// Описание гермокомплекта
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct GermDef {
+    pub capacity: usize, // вместимость помещения
+    pub labor: Vec<LaborDef>, // кто и сколько ставит
+}
+
+/// Весь игровой контент
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Content {
+    pub stationaries: HashMap<Stationary, StationaryDef>,
+    pub germs: HashMap<Germ, GermDef>,
+    pub pieces: HashMap<Resource, usize>, // сколько места занимает единица ресурса
+}
+
+impl Content {
+    /// Описание стационарки. Наличие всех проверено при загрузке.
+    pub fn stationary(&self, stationary: Stationary) -> &StationaryDef {
+        &self.stationaries[&stationary]
+    }
+
+    /// Описание гермы. Наличие всех проверено при загрузке.
+    pub fn germ(&self, germ: Germ) -> &GermDef {
+        &self.germs[&germ]
+    }
+
+    /// Размер единицы ресурса. Наличие всех проверено при загрузке.
+    pub fn piece(&self, resource: Resource) -> usize {
+        self.pieces[&resource]
+    }
+}
+
+lazy_static! {
+    /// Контент игры. Читается и проверяется при первом обращении.
+    pub static ref CONTENT: Content = load_content();
+}
+
+/// Прочитать файл данных. Не в wasm сначала ищем файл рядом с игрой,
+/// чтобы можно было менять баланс без пересборки, иначе берем вшитый.
+pub fn read_data_file(
+    path: &str,
+    embedded: &'static str,
+) -> String {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        if let Ok(text) = std::fs::read_to_string(path) {
+            return text;
+        }
+    }
+    let _ = path;
+    embedded.to_string()
+}
+
+/// Разобрать контент из текста
+pub fn parse_content(
+    text: &str,
+) -> Result<Content, String> {
+    ron::de::from_str(text).map_err(|e| e.to_string())
+}
+
+/// Проверить, что контент полный и непротиворечивый.
+/// Возвращает список всех найденных ошибок.
+pub fn validate_content(
+    content: &Content,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    for stationary in all_stationaries() {
+        let def = match content.stationaries.get(&stationary) {
+            Some(def) => def,
+            None => {
+                errors.push(format!("stationaries: нет описания {:?}", stationary));
+                continue;
+            },
+        };
+        if def.size == 0 {
+            errors.push(format!("stationaries.{:?}: size должен быть больше 0", stationary));
+        }
+        if def.build_power == 0 {
+            errors.push(format!("stationaries.{:?}: build_power должен быть больше 0", stationary));
+        }
+        validate_labor(&mut errors, &format!("stationaries.{:?}", stationary), &def.labor);
+        for prerequisite in def.prerequisites.iter() {
+            if *prerequisite == stationary || *prerequisite == Stationary::None {
+                errors.push(format!(
+                    "stationaries.{:?}: недопустимое требование {:?}",
+                    stationary,
+                    prerequisite,
+                ));
+            }
+        }
+    }
+    if content.stationaries.contains_key(&Stationary::None) {
+        errors.push("stationaries: None не описывается, это отсутствие оборудования".to_string());
+    }
+    for germ in all_germs() {
+        match content.germs.get(&germ) {
+            Some(def) => {
+                if def.capacity == 0 {
+                    errors.push(format!("germs.{:?}: capacity должен быть больше 0", germ));
+                }
+                validate_labor(&mut errors, &format!("germs.{:?}", germ), &def.labor);
+            },
+            None => errors.push(format!("germs: нет описания {:?}", germ)),
+        }
+    }
+    for resource in all_resources() {
+        match content.pieces.get(&resource) {
+            Some(0) => errors.push(format!("pieces.{:?}: размер должен быть больше 0", resource)),
+            Some(_) => {},
+            None => errors.push(format!("pieces: нет размера {:?}", resource)),
+        }
+    }
+    errors
+}
+
+/// Проверить трудозатраты: работа должна быть, и каждая часть не пустая
+fn validate_labor(
+    errors: &mut Vec<String>,
+    place: &str,
+    labor: &[LaborDef],
+) {
+    if labor.is_empty() {
+        errors.push(format!("{}: labor не может быть пустым", place));
+    }
+    for def in labor.iter() {
+        if def.bp == 0 {
+            errors.push(format!("{}: bp у {:?} {:?} должен быть больше 0", place, def.prof, def.tier));
+        }
+    }
+}
+
+/// Загрузить и проверить контент. С битым контентом игра не запускается.
+pub fn load_content() -> Content {
+    let text = read_data_file(CONTENT_PATH, CONTENT_EMBEDDED);
+    let content = parse_content(&text)
+        .unwrap_or_else(|e| panic!("{}: не удалось разобрать контент: {}", CONTENT_PATH, e));
+    let errors = validate_content(&content);
+    if !errors.is_empty() {
+        panic!("{}: ошибки в контенте:\n{}", CONTENT_PATH, errors.join("\n"));
+    }
+    content
+}
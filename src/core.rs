@@ -2,6 +2,8 @@ use std::hash::Hash;
 use std::fmt;
 use rand::thread_rng;
 use rand::Rng;
+use legion::Resources;
+use serde::Deserialize;
 
 /// Бросить кубы
 pub fn _d(rolls:u8, sides:u8) -> usize {
@@ -17,14 +19,19 @@ pub fn _d(rolls:u8, sides:u8) -> usize {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum SamosborError {
     NoEmptyArea,
     NotEnoughArea,
     NotEnoughResources,
+    SquadBusy,
+    SquadIncomplete,
+    ComradBusy,
+    ResearchUnavailable,
 }
 
 /// Уровень(изделия, опыта, ресурса и тп)
-#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash, PartialOrd, Ord)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash, PartialOrd, Ord, Deserialize)]
 pub enum Tier {
     NoTier, // уникальные штуки
     T1,
@@ -35,14 +42,18 @@ pub enum Tier {
 impl fmt::Display for Tier {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Tier::NoTier => write!(f, "{}", "без разряда"),
-            Tier::T1     => write!(f, "{}", "1 разряда"),
-            Tier::T2     => write!(f, "{}", "2 разряда"),
-            Tier::T3     => write!(f, "{}", "3 разряда"),
+            Tier::NoTier => write!(f, "без разряда"),
+            Tier::T1     => write!(f, "1 разряда"),
+            Tier::T2     => write!(f, "2 разряда"),
+            Tier::T3     => write!(f, "3 разряда"),
         }
     }
 }
 
+/// Сколько смен осталось до окончания процесса
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ShiftsLeft(pub usize);
+
 /// В каком состоянии строение
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum TaskStatus {
@@ -53,8 +64,27 @@ pub enum TaskStatus {
 impl fmt::Display for TaskStatus {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            TaskStatus::Constructing => write!(f, "{}", "Строится"),
-            TaskStatus::Ready        => write!(f, "{}", "Готово"),
+            TaskStatus::Constructing => write!(f, "Строится"),
+            TaskStatus::Ready        => write!(f, "Готово"),
         }
     }
 }
+
+/// Что важного случилось за смену
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct TurnReport(pub Vec<String>);
+
+/// Записать событие в сводку за смену
+pub fn report(
+    resources: &mut Resources,
+    event: String,
+) {
+    if resources.get::<TurnReport>().is_none() {
+        resources.insert(TurnReport::default());
+    }
+    resources
+        .get_mut::<TurnReport>()
+        .unwrap()
+        .0
+        .push(event);
+}
@@ -0,0 +1,315 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use legion::*;
+
+use crate::core::*;
+use crate::people::*;
+use crate::population::*;
+use crate::production::*;
+use crate::resources::*;
+use crate::storage::*;
+use crate::turn::*;
+use crate::unrest::*;
+
+/// Сколько стоит пачка концентрата сверх пайка
+pub static FOOD_PRICE: usize = 3;
+
+/// Сколько стоит смена в нейротерминале
+pub static LEISURE_PRICE: usize = 5;
+
+/// Сколько стоит вещь из полимера
+pub static GOODS_PRICE: usize = 20;
+
+/// Сколько смен радует купленная вещь
+pub static GOODS_JOY_SHIFTS: usize = 30;
+
+/// За сколько освоенных трудочасов в кассу поступает один талон
+pub static BP_PER_COUPON: usize = 4;
+
+/// Сколько талонов в кассе на старте
+pub static COUPON_FUND_START: usize = 500;
+
+/// Касса блока: сколько талонов еще можно выдать.
+/// Выпуск талонов обеспечен выработкой, а не печатным станком.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CouponFund(pub usize);
+
+impl Default for CouponFund {
+    fn default() -> Self {
+        Self(COUPON_FUND_START)
+    }
+}
+
+/// Талоны на руках у комрада
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Wallet {
+    pub coupons: usize,
+    pub wage: usize, // сколько заплатили за последнюю смену
+    pub expected: usize, // на сколько рассчитывал
+    pub joy_shifts: usize, // сколько еще смен радуют покупки
+}
+
+/// Ставки оплаты в талонах за смену по профессии и разряду
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PayScale(pub HashMap<(Profession, Tier), usize>);
+
+impl Default for PayScale {
+    fn default() -> Self {
+        let mut scale = HashMap::new();
+        for prof in all_professions() {
+            for tier in [Tier::T1, Tier::T2, Tier::T3].iter() {
+                scale.insert((prof, *tier), expected_wage(prof, *tier));
+            }
+        }
+        Self(scale)
+    }
+}
+
+impl PayScale {
+    /// Сколько платят за смену комраду этой профессии и разряда
+    pub fn wage(&self, prof: Profession, tier: Tier) -> usize {
+        self.0.get(&(prof, tier)).copied().unwrap_or(0)
+    }
+}
+
+/// На какую оплату комрад рассчитывает. Тунеядцу ничего не положено.
+pub fn expected_wage(
+    prof: Profession,
+    tier: Tier,
+) -> usize {
+    match (prof, tier) {
+        (Profession::NoProf, _) => 0,
+        (_, Tier::NoTier) => 0,
+        (_, Tier::T1) => 2,
+        (_, Tier::T2) => 4,
+        (_, Tier::T3) => 7,
+    }
+}
+
+/// Как оплата влияет на настроение
+pub fn wage_mood_factor(
+    wallet: Option<&Wallet>,
+) -> i8 {
+    let (wage, expected) = match wallet {
+        Some(w) => (w.wage, w.expected),
+        None => return 0,
+    };
+    if expected == 0 {
+        0
+    } else if wage == 0 {
+        -2
+    } else if wage < expected {
+        -1
+    } else if wage >= expected * 2 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Как покупки влияют на настроение
+pub fn purchases_mood_factor(
+    wallet: Option<&Wallet>,
+) -> i8 {
+    if wallet.map(|w| w.joy_shifts > 0).unwrap_or(false) { 1 } else { 0 }
+}
+
+/// Талоны за смену: пополнить кассу выработкой, выдать зарплату и потратить.
+/// Бастующим, детям и пенсионерам не платят, и они этого не ждут.
+/// Если в кассе не хватает на всю зарплату - всем платят поровну урезанную.
+/// Голодный сначала докупает концентрат, потом копит на вещь из полимера,
+/// а если вещи нет - идет в нейротерминал.
+pub fn coupons_tick(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    let scale = resources
+        .get::<PayScale>()
+        .map(|s| s.clone())
+        .unwrap_or_default();
+    let worked = resources
+        .get::<LaborStats>()
+        .map(|l| l.actual.0)
+        .unwrap_or(0);
+    let mut fund = resources
+        .get::<CouponFund>()
+        .map(|f| *f)
+        .unwrap_or_default();
+    fund.0 += worked / BP_PER_COUPON;
+    let total = payroll(world, &scale);
+    let pay = |wage: usize| if total > fund.0 { wage * fund.0 / total } else { wage };
+    let mut paid = 0;
+    let mut concentrat = how_much_we_have(world, Resource::Concentrat).0;
+    let mut polymer = how_much_we_have(world, Resource::Polymer).0;
+    let mut terminals_query = <(&Stationary, &TaskStatus)>::query();
+    let has_terminal = terminals_query
+        .iter(world)
+        .any(|(s, status)| *s == Stationary::NeuroTerminal && *status == TaskStatus::Ready);
+    let mut unpaid_query = <Entity>::query()
+        .filter(component::<Child>() | component::<Retired>() | component::<OnStrike>());
+    let unpaid: HashSet<Entity> = unpaid_query.iter(world).copied().collect();
+
+    let mut food_sold = 0;
+    let mut goods_sold = 0;
+    let mut query = <(
+        Entity,
+        &Profession,
+        &Tier,
+        &mut Satiety,
+        &mut Wallet,
+    )>::query();
+    for (entity, prof, tier, sat, wallet) in query.iter_mut(world) {
+        if unpaid.contains(entity) {
+            wallet.wage = 0;
+            wallet.expected = 0;
+        } else {
+            wallet.wage = pay(scale.wage(*prof, *tier));
+            wallet.expected = expected_wage(*prof, *tier);
+            wallet.coupons += wallet.wage;
+            paid += wallet.wage;
+        }
+        wallet.joy_shifts = wallet.joy_shifts.saturating_sub(1);
+        if sat.0 < SATIETY_HUNGER && wallet.coupons >= FOOD_PRICE && concentrat > 0 {
+            wallet.coupons -= FOOD_PRICE;
+            concentrat -= 1;
+            food_sold += 1;
+            *sat = eat_pack(*sat);
+        }
+        if wallet.joy_shifts > 0 {
+            continue;
+        }
+        if wallet.coupons >= GOODS_PRICE && polymer > 0 {
+            wallet.coupons -= GOODS_PRICE;
+            polymer -= 1;
+            goods_sold += 1;
+            wallet.joy_shifts = GOODS_JOY_SHIFTS;
+        } else if wallet.coupons >= LEISURE_PRICE && has_terminal {
+            wallet.coupons -= LEISURE_PRICE;
+            wallet.joy_shifts = 1;
+        }
+    }
+    writeoff(world, Resource::Concentrat, RealUnits(food_sold));
+    writeoff(world, Resource::Polymer, RealUnits(goods_sold));
+    resources.insert(CouponFund(fund.0.saturating_sub(paid)));
+}
+
+/// Сколько талонов уходит на зарплату за смену
+pub fn payroll(
+    world: &World,
+    scale: &PayScale,
+) -> usize {
+    let mut query = <(&Profession, &Tier)>::query()
+        .filter(
+            component::<Wallet>() &
+            !component::<Child>() &
+            !component::<Retired>() &
+            !component::<OnStrike>()
+        );
+    query
+        .iter(world)
+        .map(|(prof, tier)| scale.wage(*prof, *tier))
+        .sum()
+}
+
+/// Сколько талонов на руках у населения
+pub fn coupons_in_circulation(
+    world: &World,
+) -> usize {
+    let mut query = <&Wallet>::query();
+    query.iter(world).map(|w| w.coupons).sum()
+}
+
+/// Сколько людей какой профессии и разряда получают зарплату
+pub fn paid_by_profession(
+    world: &World,
+) -> HashMap<(Profession, Tier), usize> {
+    let mut result = HashMap::new();
+    let mut query = <(&Profession, &Tier)>::query()
+        .filter(
+            component::<Wallet>() &
+            !component::<Child>() &
+            !component::<Retired>() &
+            !component::<OnStrike>()
+        );
+    for (prof, tier) in query.iter(world) {
+        *result.entry((*prof, *tier)).or_insert(0) += 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wallet(world: &World, comrad: Entity) -> Wallet {
+        *world.entry_ref(comrad).unwrap().get_component::<Wallet>().unwrap()
+    }
+
+    #[test]
+    fn strikers_are_not_on_payroll() {
+        let mut world = World::default();
+        let room = world.push((Satiety(0),));
+        spawn_comrad(&mut world, Profession::Worker, Tier::T1, room);
+        spawn_comrad(&mut world, Profession::Scientist, Tier::T3, room);
+        let striker = spawn_comrad(&mut world, Profession::Worker, Tier::T2, room);
+        if let Some(mut entry) = world.entry(striker) {
+            entry.add_component(OnStrike);
+        }
+        let scale = PayScale::default();
+        assert_eq!(payroll(&world, &scale), 2 + 7);
+        let paid = paid_by_profession(&world);
+        assert_eq!(paid.get(&(Profession::Worker, Tier::T1)), Some(&1));
+        assert_eq!(paid.get(&(Profession::Worker, Tier::T2)), None);
+    }
+
+    #[test]
+    fn wages_come_from_fund() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let room = world.push((Satiety(0),));
+        let worker = spawn_comrad(&mut world, Profession::Worker, Tier::T1, room);
+        let striker = spawn_comrad(&mut world, Profession::Worker, Tier::T3, room);
+        if let Some(mut entry) = world.entry(striker) {
+            entry.add_component(OnStrike);
+        }
+        resources.insert(CouponFund(10));
+        coupons_tick(&mut world, &mut resources);
+        assert_eq!(wallet(&world, worker), Wallet { coupons: 2, wage: 2, expected: 2, joy_shifts: 0 });
+        assert_eq!(wallet(&world, striker), Wallet::default());
+        assert_eq!(*resources.get::<CouponFund>().unwrap(), CouponFund(8));
+        assert_eq!(coupons_in_circulation(&world), 2);
+    }
+
+    #[test]
+    fn short_fund_cuts_wages_for_all() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let room = world.push((Satiety(0),));
+        let first = spawn_comrad(&mut world, Profession::Worker, Tier::T2, room);
+        let second = spawn_comrad(&mut world, Profession::Worker, Tier::T2, room);
+        resources.insert(CouponFund(0));
+        resources.insert(LaborStats {
+            actual: BuildPower(4 * BP_PER_COUPON),
+            ..LaborStats::default()
+        });
+        coupons_tick(&mut world, &mut resources);
+        // выработка дала 4 талона на зарплату в 8
+        assert_eq!(wallet(&world, first).wage, 2);
+        assert_eq!(wallet(&world, second).wage, 2);
+        assert_eq!(wage_mood_factor(Some(&wallet(&world, first))), -1);
+        assert_eq!(*resources.get::<CouponFund>().unwrap(), CouponFund(0));
+    }
+
+    #[test]
+    fn wage_mood() {
+        let w = |wage, expected| Wallet { coupons: 0, wage, expected, joy_shifts: 0 };
+        assert_eq!(wage_mood_factor(None), 0);
+        assert_eq!(wage_mood_factor(Some(&w(0, 0))), 0);
+        assert_eq!(wage_mood_factor(Some(&w(0, 2))), -2);
+        assert_eq!(wage_mood_factor(Some(&w(2, 2))), 0);
+        assert_eq!(wage_mood_factor(Some(&w(4, 2))), 1);
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use legion::*;
+
+use crate::area::*;
+use crate::core::*;
+use crate::experience::*;
+use crate::people::*;
+use crate::population::*;
+use crate::production::*;
+use crate::resources::*;
+use crate::squad::*;
+use crate::storage::*;
+
+/// Курс переобучения одного комрада.
+/// Идет в партпомещении и занимает там одно учебное место.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Course {
+    pub student: Entity,
+    pub target: Profession, // на кого учим
+}
+
+/// Метка того, что комрад сейчас учится и не работает
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InTraining(pub Entity);
+
+/// Сколько смен идет курс. На тунеядца учить не надо.
+pub fn course_duration(
+    target: Profession,
+) -> usize {
+    match target {
+        Profession::NoProf     => 0,
+        Profession::Stalker    => 3,
+        Profession::Worker     => 3,
+        Profession::Likvidator => 5,
+        Profession::Party      => 7,
+        Profession::Scientist  => 10,
+    }
+}
+
+/// Преподавание: партийный функционер 1 разряда ведет курс все время обучения
+pub fn course_requirements(
+    target: Profession,
+) -> HashSet<TaskMeta> {
+    [
+        TaskMeta {
+            prof: Profession::Party,
+            tier: Tier::T1,
+            bp: BuildPower(10 * course_duration(target)),
+            stationary: Stationary::None,
+        },
+    ].iter().cloned().collect()
+}
+
+/// Ученика надо кормить сверх пайка: пачка концентрата за смену учебы
+pub fn course_cost(
+    target: Profession,
+) -> HashMap<Resource, RealUnits> {
+    [
+        (Resource::Concentrat, RealUnits(course_duration(target))),
+    ].iter().cloned().collect()
+}
+
+/// Записать комрада на переобучение. Детей и пенсионеров не учим,
+/// бойцов с задания не отзываем, а из отряда в казарме отчисляем.
+/// Нужно свободное учебное место в партпомещении и концентрат.
+pub fn enroll(
+    world: &mut World,
+    student: Entity,
+    target: Profession,
+) -> Result<Entity, SamosborError> {
+    let busy = world
+        .entry_ref(student)
+        .map(|entry| {
+            entry.get_component::<InTraining>().is_ok()
+                || entry.get_component::<Child>().is_ok()
+                || entry.get_component::<Retired>().is_ok()
+        })
+        .unwrap_or(true);
+    if busy || fighters_on_mission(world).contains(&student) {
+        return Err(SamosborError::ComradBusy);
+    }
+    let room = get_sufficent_room(
+        world,
+        AreaOccupied(COMRAD_RENTED_PLACE),
+        AreaType::Party,
+    ).ok_or(SamosborError::NotEnoughArea)?;
+    writeoff_bunch(world, course_cost(target))?;
+    leave_squad(world, student);
+    let course = world.push((
+        Course { student, target },
+        BelongsToRoom(room),
+        AreaOccupied(COMRAD_RENTED_PLACE),
+        ShiftsLeft(course_duration(target)),
+        TaskPriority(0),
+        TaskStatus::Constructing,
+        task_meta2progress(course_requirements(target)),
+    ));
+    if let Some(mut entry) = world.entry(student) {
+        entry.add_component(InTraining(course));
+    }
+    record_history(world, student, format!("Направление на курсы: {}", target));
+    Ok(course)
+}
+
+/// Отсчитать смену курсам. Выпускники получают новую профессию 1 разряда.
+/// Возвращает выпускников и их новую профессию.
+pub fn finish_courses(
+    world: &mut World,
+) -> Vec<(Entity, Profession)> {
+    let mut finished = Vec::new();
+    let mut query = <(
+        Entity,
+        &Course,
+        &mut ShiftsLeft,
+        &TaskStatus,
+    )>::query();
+    for (entity, course, shifts_left, status) in query.iter_mut(world) {
+        shifts_left.0 = shifts_left.0.saturating_sub(1);
+        if *status == TaskStatus::Ready && shifts_left.0 == 0 {
+            finished.push((*entity, *course));
+        }
+    }
+    // ученик мог умереть или сбежать, не доучившись
+    let mut orphaned = Vec::new();
+    let mut query = <(Entity, &Course)>::query();
+    for (entity, course) in query.iter(world) {
+        if !world.contains(course.student) {
+            orphaned.push(*entity);
+        }
+    }
+    for entity in orphaned {
+        world.remove(entity);
+    }
+
+    let mut graduates = Vec::new();
+    for (entity, course) in finished {
+        world.remove(entity);
+        if let Some(mut entry) = world.entry(course.student) {
+            entry.remove_component::<InTraining>();
+            entry.add_component(course.target);
+            entry.add_component(Tier::T1);
+            entry.add_component(Experience(0));
+        } else {
+            continue;
+        }
+        record_history(world, course.student, format!("Окончание курсов: {}", course.target));
+        graduates.push((course.student, course.target));
+    }
+    graduates
+}
+
+/// Кто сейчас учится: ученик, на кого, прогресс преподавания, сколько смен осталось
+pub fn courses_in_progress(
+    world: &World,
+) -> Vec<(Entity, Profession, Option<TaskProgress>, usize)> {
+    let mut query = <(&Course, Option<&TaskProgress>, &ShiftsLeft)>::query();
+    query
+        .iter(world)
+        .map(|(course, progress, shifts_left)| {
+            (course.student, course.target, progress.cloned(), shifts_left.0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ready_room(world: &mut World, germ: Germ, purpose: AreaType) -> Entity {
+        let room = install_germ(world, germ, purpose);
+        if let Some(mut entry) = world.entry(room) {
+            entry.add_component(TaskStatus::Ready);
+            entry.remove_component::<TaskProgress>();
+        }
+        room
+    }
+
+    #[test]
+    fn fighters_leave_squad_for_courses() {
+        let mut world = World::default();
+        ready_room(&mut world, Germ::GermT2, AreaType::Party);
+        let barracks = ready_room(&mut world, Germ::GermT2, AreaType::Military);
+        put_resource(&mut world, Resource::Concentrat, RealUnits(1000));
+        let squad = spawn_1_g(&mut world, barracks);
+        let sergeant = squad_members(&world, squad)
+            .into_iter()
+            .find(|(role, _, _)| *role == SquadRole::Sergeant)
+            .map(|(_, _, comrad)| comrad)
+            .unwrap();
+
+        dispatch_squad(&mut world, squad, Mission::Patrol).unwrap();
+        assert_eq!(enroll(&mut world, sergeant, Profession::Worker), Err(SamosborError::ComradBusy));
+        missions_tick(&mut world);
+
+        assert!(enroll(&mut world, sergeant, Profession::Worker).is_ok());
+        let entry = world.entry_ref(sergeant).unwrap();
+        assert!(entry.get_component::<InSquad>().is_err());
+        assert!(entry.get_component::<InTraining>().is_ok());
+        assert!(world.entry_ref(squad).unwrap().get_component::<SquadCommander>().is_err());
+        assert_eq!(validate_squad(&world, squad).unwrap_err().get(&SquadRole::Sergeant), Some(&1));
+    }
+}
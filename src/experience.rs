@@ -0,0 +1,155 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use legion::*;
+
+use crate::area::*;
+use crate::core::*;
+use crate::people::*;
+use crate::production::*;
+use crate::housing::*;
+use crate::unrest::*;
+use crate::inspection::*;
+
+/// Опыт комрада: сколько трудочасов он вложил в задания своей профессии
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Experience(pub usize);
+
+/// Кто сколько трудочасов внес в пул за смену
+pub type LaborContributions = HashMap<Entity, (Profession, Tier, BuildPower)>;
+
+/// Сколько опыта нужно, чтобы получить следующий разряд.
+/// T1 рабочий набирает 1000 примерно за сто смен.
+pub fn experience_for_promotion(
+    tier: Tier,
+) -> Option<Experience> {
+    match tier {
+        Tier::NoTier => None,
+        Tier::T1     => Some(Experience(1000)),
+        Tier::T2     => Some(Experience(4000)),
+        Tier::T3     => None,
+    }
+}
+
+/// Где учат на следующий разряд.
+/// На 3 разряд без учебы не повысить.
+pub fn training_room(
+    prof: Profession,
+    next_tier: Tier,
+) -> Option<AreaType> {
+    match (prof, next_tier) {
+        (Profession::Scientist, Tier::T3) => Some(AreaType::Science),
+        (_, Tier::T3)                     => Some(AreaType::Party),
+        _                                 => None,
+    }
+}
+
+fn next_tier(
+    tier: Tier,
+) -> Option<Tier> {
+    match tier {
+        Tier::T1 => Some(Tier::T2),
+        Tier::T2 => Some(Tier::T3),
+        _        => None,
+    }
+}
+
+/// Начислить опыт за трудочасы, которые реально ушли в задания.
+/// Если пул профессии освоен не полностью - опыт делится пропорционально вкладу.
+pub fn gain_experience(
+    world: &mut World,
+    contributions: &LaborContributions,
+    pool_left: &HashMap<Profession, HashMap<Tier, BuildPower>>,
+) {
+    let mut contributed: HashMap<(Profession, Tier), usize> = HashMap::new();
+    for (prof, tier, bp) in contributions.values() {
+        *contributed.entry((*prof, *tier)).or_insert(0) += bp.0;
+    }
+    for (comrad, (prof, tier, bp)) in contributions.iter() {
+        let total = contributed[&(*prof, *tier)];
+        let left = pool_left
+            .get(prof)
+            .and_then(|by_tier| by_tier.get(tier))
+            .map(|bp| bp.0)
+            .unwrap_or(0);
+        let spent = total.saturating_sub(left);
+        let gained = (bp.0 * spent).checked_div(total).unwrap_or(0);
+        if gained == 0 {
+            continue;
+        }
+        if let Some(mut entry) = world.entry(*comrad) {
+            match entry.get_component_mut::<Experience>() {
+                Ok(exp) => exp.0 += gained,
+                Err(_) => entry.add_component(Experience(gained)),
+            }
+        }
+    }
+}
+
+/// Сколько повышений до 3 разряда могут принять за смену:
+/// один партийный функционер 2 разряда и выше готовит одного человека,
+/// если он не болеет, не бастует и не сидит на складе
+pub fn examiners_count(
+    world: &World,
+) -> usize {
+    let mut query = <(&Profession, &Tier)>::query()
+        .filter(!component::<OnStrike>() & !component::<Sick>() & !component::<Storekeeper>());
+    query
+        .iter(world)
+        .filter(|(prof, tier)| **prof == Profession::Party && **tier >= Tier::T2)
+        .count()
+}
+
+/// Повысить разряд тем, кто набрал опыта. Опыт после повышения копится заново.
+/// Для повышения до 3 разряда нужны свободное учебное место в готовом
+/// помещении для учебы и свободный экзаменатор: каждое место и каждый
+/// экзаменатор - одно повышение за смену.
+/// Возвращает повышенных: комрад, профессия, новый разряд.
+pub fn promotions_tick(
+    world: &mut World,
+) -> Vec<(Entity, Profession, Tier)> {
+    // свободные учебные места по типам помещений
+    let mut seats: HashMap<AreaType, usize> = HashMap::new();
+    for (atype, _, free, _) in all_rooms_with_space(world).values() {
+        *seats.entry(*atype).or_insert(0) += free.0 / COMRAD_RENTED_PLACE;
+    }
+    let mut examiners = examiners_count(world);
+
+    let mut query = <(Entity, &Profession, &Tier, &Experience)>::query();
+    let mut candidates: Vec<(Entity, Profession, Tier, Experience)> = query
+        .iter(world)
+        .filter_map(|(comrad, prof, tier, exp)| {
+            let required = experience_for_promotion(*tier)?;
+            let next = next_tier(*tier)?;
+            if *exp < required {
+                return None;
+            }
+            Some((*comrad, *prof, next, *exp))
+        })
+        .collect();
+    // учебные места достаются самым опытным
+    candidates.sort_by_key(|(_, _, _, exp)| Reverse(*exp));
+
+    let mut promoted = Vec::new();
+    for (comrad, prof, next, _) in candidates {
+        if let Some(room) = training_room(prof, next) {
+            match seats.get_mut(&room) {
+                Some(free) if *free > 0 && examiners > 0 => {
+                    *free -= 1;
+                    examiners -= 1;
+                },
+                _ => continue,
+            }
+        }
+        promoted.push((comrad, prof, next));
+    }
+
+    for (comrad, _, next) in promoted.iter() {
+        if let Some(mut entry) = world.entry(*comrad) {
+            entry.add_component(*next);
+            entry.add_component(Experience(0));
+        }
+        record_history(world, *comrad, format!("Повышение до {}", next));
+    }
+    promoted
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use legion::*;
+
+use crate::area::*;
+use crate::core::*;
+use crate::people::*;
+
+/// Сколько смен длится болезнь
+pub static SICK_SHIFTS: usize = 5;
+
+/// На сколько процентов за смену больной сосед повышает риск заразиться
+pub static SICK_CONTAGION: usize = 5;
+
+/// Комрад болен и не работает. Сколько смен еще болеть.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sick(pub usize);
+
+/// Заполненность жилячейки: доля занятой площади
+pub fn crowding(
+    occupied: AreaOccupied,
+    capacity: AreaCapacity,
+) -> f32 {
+    occupied.0 as f32 / capacity.0.max(1) as f32
+}
+
+/// Как теснота влияет на риск заболеть (процентов за смену).
+/// В полупустой ячейке не болеют, в набитой битком болеют часто.
+pub fn disease_chance(
+    occupied: AreaOccupied,
+    capacity: AreaCapacity,
+) -> usize {
+    let ratio = crowding(occupied, capacity);
+    if ratio > 1.0 {
+        10
+    } else if ratio >= 0.9 {
+        2
+    } else {
+        0
+    }
+}
+
+/// Заполненность всех готовых жилячеек
+pub fn living_rooms_crowding(
+    world: &mut World,
+) -> HashMap<Entity, (AreaCapacity, AreaOccupied)> {
+    all_rooms_with_space(world)
+        .into_iter()
+        .filter(|(_, (atype, _, _, _))| *atype == AreaType::Living)
+        .map(|(room, (_, capacity, _, occupied))| (room, (capacity, occupied)))
+        .collect()
+}
+
+/// Найти наименее заполненную жилячейку,
+/// в которую поместится еще один жилец такого размера
+pub fn least_crowded_living_room(
+    world: &mut World,
+    place: usize,
+) -> Option<Entity> {
+    let mut rooms: Vec<(Entity, f32)> = living_rooms_crowding(world)
+        .into_iter()
+        .filter(|(_, (capacity, occupied))| {
+            occupied.0 + place <= capacity.0
+        })
+        .map(|(room, (capacity, occupied))| (room, crowding(occupied, capacity)))
+        .collect();
+    rooms.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+    rooms.first().map(|(room, _)| *room)
+}
+
+/// Заселить нового комрада в наименее заполненную жилячейку
+pub fn settle_comrad(
+    world: &mut World,
+    prof: Profession,
+    tier: Tier,
+) -> Result<Entity, SamosborError> {
+    match least_crowded_living_room(world, COMRAD_RENTED_PLACE) {
+        Some(room) => Ok(spawn_comrad(world, prof, tier, room)),
+        None => Err(SamosborError::NotEnoughArea),
+    }
+}
+
+/// Переселить комрада в наименее заполненную жилячейку,
+/// если там ему будет просторнее, чем сейчас
+pub fn rehouse_comrad(
+    world: &mut World,
+    comrad: Entity,
+) -> Result<Entity, SamosborError> {
+    let rooms = living_rooms_crowding(world);
+    let current = world
+        .entry_ref(comrad)
+        .ok()
+        .and_then(|entry| entry.get_component::<BelongsToRoom>().ok().copied())
+        .map(|BelongsToRoom(room)| room);
+    let target = least_crowded_living_room(world, COMRAD_RENTED_PLACE)
+        .ok_or(SamosborError::NotEnoughArea)?;
+    if let Some(room) = current {
+        if room == target {
+            return Ok(room);
+        }
+        if let (Some((cur_cap, cur_occ)), Some((new_cap, new_occ))) = (rooms.get(&room), rooms.get(&target)) {
+            let after_move = AreaOccupied(new_occ.0 + COMRAD_RENTED_PLACE);
+            if crowding(after_move, *new_cap) >= crowding(*cur_occ, *cur_cap) {
+                return Ok(room);
+            }
+        }
+    }
+    if let Some(mut entry) = world.entry(comrad) {
+        entry.add_component(BelongsToRoom(target));
+    }
+    record_history(world, comrad, "Переселение в другую жилячейку".to_string());
+    Ok(target)
+}
+
+/// Расселить переполненные жилячейки.
+/// Возвращает, сколько человек переехало.
+pub fn rehouse_crowded(
+    world: &mut World,
+) -> usize {
+    let rooms = living_rooms_crowding(world);
+    let mut query = <(Entity, &Profession, &BelongsToRoom)>::query();
+    let candidates: Vec<Entity> = query
+        .iter(world)
+        .filter(|(_, _, BelongsToRoom(room))| {
+            rooms
+                .get(room)
+                .map(|(capacity, occupied)| crowding(*occupied, *capacity) >= 0.9)
+                .unwrap_or(false)
+        })
+        .map(|(e, _, _)| *e)
+        .collect();
+    let mut moved = 0;
+    for comrad in candidates {
+        let before = world
+            .entry_ref(comrad)
+            .ok()
+            .and_then(|entry| entry.get_component::<BelongsToRoom>().ok().copied());
+        if let Ok(room) = rehouse_comrad(world, comrad) {
+            if before != Some(BelongsToRoom(room)) {
+                moved += 1;
+            }
+        }
+    }
+    moved
+}
+
+/// Болезни за смену: больные выздоравливают,
+/// в тесных ячейках заражаются новые
+pub fn disease_tick(
+    world: &mut World,
+) {
+    let rooms = living_rooms_crowding(world);
+
+    let mut sick_query = <(Entity, &BelongsToRoom, &mut Sick)>::query();
+    let mut recovered = Vec::new();
+    let mut sick_by_room: HashMap<Entity, usize> = HashMap::new();
+    for (entity, BelongsToRoom(room), Sick(left)) in sick_query.iter_mut(world) {
+        *left = left.saturating_sub(1);
+        if *left == 0 {
+            recovered.push(*entity);
+        } else {
+            *sick_by_room.entry(*room).or_insert(0) += 1;
+        }
+    }
+    for e in recovered {
+        if let Some(mut entry) = world.entry(e) {
+            entry.remove_component::<Sick>();
+        }
+    }
+
+    let mut query = <(Entity, &Profession, &BelongsToRoom)>::query();
+    let mut infected = Vec::new();
+    for (entity, _, BelongsToRoom(room)) in query.iter(world) {
+        if let Some((capacity, occupied)) = rooms.get(room) {
+            let chance = disease_chance(*occupied, *capacity)
+                + SICK_CONTAGION * sick_by_room.get(room).unwrap_or(&0);
+            if _d(1, 100) < chance {
+                infected.push(*entity);
+            }
+        }
+    }
+    for e in infected {
+        if let Some(mut entry) = world.entry(e) {
+            if entry.get_component::<Sick>().is_ok() {
+                continue;
+            }
+            entry.add_component(Sick(SICK_SHIFTS));
+        }
+        record_history(world, e, "Заболевание".to_string());
+    }
+}
+
+/// Сколько больных в каждой комнате
+pub fn sick_by_room(
+    world: &World,
+) -> HashMap<Entity, usize> {
+    let mut result = HashMap::new();
+    let mut query = <(&BelongsToRoom, &Sick)>::query();
+    for (BelongsToRoom(room), _) in query.iter(world) {
+        *result.entry(*room).or_insert(0) += 1;
+    }
+    result
+}
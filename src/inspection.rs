@@ -0,0 +1,402 @@
+use std::fmt;
+use std::collections::HashMap;
+
+use legion::*;
+use rand::thread_rng;
+use rand::seq::SliceRandom;
+
+use crate::accounting::*;
+use crate::area::*;
+use crate::core::*;
+use crate::education::*;
+use crate::experience::*;
+use crate::housing::*;
+use crate::people::*;
+use crate::population::*;
+use crate::resources::*;
+use crate::squad::*;
+use crate::storage::*;
+use crate::trust::*;
+
+/// Как часто приходит партийная комиссия (смен)
+pub static INSPECTION_PERIOD: usize = 60;
+
+/// Как часто приходит комиссия, если доверие низкое (смен)
+pub static INSPECTION_PERIOD_DISTRUST: usize = 15;
+
+/// Ниже какого доверия комиссии зачастят
+pub static TRUST_INSPECTION: u8 = 30;
+
+/// За сколькими единицами объема склада может уследить один кладовщик
+pub static STOREKEEPER_VOLUME: usize = 10000;
+
+/// Какую долю (в процентах) каждого ресурса изымают за каждое нарушение
+pub static INSPECTION_FINE: usize = 5;
+
+/// Какая доля больных (в процентах) считается антисанитарией
+pub static INSPECTION_SICK_SHARE: usize = 10;
+
+/// Сколько доверия стоит каждое нарушение
+pub static INSPECTION_PENALTY: u8 = 3;
+
+/// Сколько доверия дают за чистую проверку
+pub static INSPECTION_REWARD: u8 = 5;
+
+/// После скольких нарушений комиссия арестовывает функционера
+pub static INSPECTION_ARREST: usize = 2;
+
+/// Что проверяет комиссия
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum InspectionArea {
+    Storage, // учет на складах
+    Sanitation, // теснота и болезни в жилячейках
+    Squads, // готовность отрядов
+}
+
+impl fmt::Display for InspectionArea {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InspectionArea::Storage    => write!(f, "Склады"),
+            InspectionArea::Sanitation => write!(f, "Санитария"),
+            InspectionArea::Squads     => write!(f, "Отряды"),
+        }
+    }
+}
+
+/// Вывод комиссии по одному направлению
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    pub area: InspectionArea,
+    pub passed: bool,
+    pub note: String,
+}
+
+/// Акт проверки
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct InspectionReport {
+    pub findings: Vec<Finding>,
+    pub fines: HashMap<Resource, RealUnits>, // что изъято
+    pub promoted: Vec<String>, // кого из функционеров повысили
+    pub arrested: Vec<String>, // кого из функционеров арестовали
+    pub trust: i16, // как изменилось доверие
+}
+
+impl InspectionReport {
+    /// Сколько нарушений нашли
+    pub fn violations(&self) -> usize {
+        self.findings.iter().filter(|f| !f.passed).count()
+    }
+}
+
+/// Когда придет комиссия и что она нашла в прошлый раз
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Inspections {
+    pub next_in: usize,
+    pub last: Option<InspectionReport>,
+}
+
+impl Default for Inspections {
+    fn default() -> Self {
+        Self {
+            next_in: INSPECTION_PERIOD,
+            last: None,
+        }
+    }
+}
+
+/// Раз в сколько смен приходит комиссия при таком доверии
+pub fn inspection_period(
+    trust: PartyTrust,
+) -> usize {
+    if trust.0 < TRUST_INSPECTION {
+        INSPECTION_PERIOD_DISTRUST
+    } else {
+        INSPECTION_PERIOD
+    }
+}
+
+/// Метка кладовщика: функционер закреплен за складами
+/// и ни на какой другой работе не занят
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Storekeeper;
+
+/// Работающие партийные функционеры. Дети и пенсионеры не в счет.
+pub fn functionaries(
+    world: &World,
+) -> Vec<Entity> {
+    let mut query = <(Entity, &Profession)>::query()
+        .filter(!component::<Child>() & !component::<Retired>());
+    query
+        .iter(world)
+        .filter(|(_, prof)| **prof == Profession::Party)
+        .map(|(e, _)| *e)
+        .collect()
+}
+
+/// Функционеры, назначенные кладовщиками
+pub fn storekeepers(
+    world: &World,
+) -> Vec<Entity> {
+    let mut query = <Entity>::query()
+        .filter(component::<Storekeeper>() & !component::<Child>() & !component::<Retired>());
+    query.iter(world).copied().collect()
+}
+
+/// Назначить кладовщиком свободного функционера.
+/// Берем младшего по разряду: старшие нужнее экзаменаторами.
+pub fn assign_storekeeper(
+    world: &mut World,
+) -> Result<Entity, SamosborError> {
+    let mut query = <(Entity, &Tier)>::query()
+        .filter(!component::<Storekeeper>() & !component::<InTraining>());
+    let staff = functionaries(world);
+    let comrad = query
+        .iter(world)
+        .filter(|(e, _)| staff.contains(e))
+        .min_by_key(|(_, tier)| **tier)
+        .map(|(e, _)| *e)
+        .ok_or(SamosborError::ComradBusy)?;
+    if let Some(mut entry) = world.entry(comrad) {
+        entry.add_component(Storekeeper);
+    }
+    record_history(world, comrad, "Назначение кладовщиком".to_string());
+    Ok(comrad)
+}
+
+/// Снять с должности одного кладовщика
+pub fn dismiss_storekeeper(
+    world: &mut World,
+) -> Option<Entity> {
+    let comrad = *storekeepers(world).first()?;
+    if let Some(mut entry) = world.entry(comrad) {
+        entry.remove_component::<Storekeeper>();
+    }
+    record_history(world, comrad, "Снятие с должности кладовщика".to_string());
+    Some(comrad)
+}
+
+/// Сколько места на складах занято ресурсами
+pub fn stored_volume(
+    world: &World,
+) -> usize {
+    let mut query = <(&Resource, &AreaOccupied)>::query();
+    query.iter(world).map(|(_, occupied)| occupied.0).sum()
+}
+
+/// Сколько кладовщиков нужно на такой объем склада
+pub fn storekeepers_required(
+    volume: usize,
+) -> usize {
+    volume.div_ceil(STOREKEEPER_VOLUME)
+}
+
+/// Хватает ли кладовщиков на объем склада и сходится ли учет с наличием
+fn inspect_storage(
+    world: &World,
+    audit: &AuditResult,
+) -> Finding {
+    let required = storekeepers_required(stored_volume(world));
+    let storekeepers = storekeepers(world).len();
+    let shortage: usize = audit.shortage().values().map(|a| a.0).sum();
+    Finding {
+        area: InspectionArea::Storage,
+        passed: storekeepers >= required && shortage == 0,
+        note: format!(
+            "Кладовщиков {} из {} положенных, недостача: {} ед.",
+            storekeepers,
+            required,
+            shortage,
+        ),
+    }
+}
+
+/// Нет ли переполненных жилячеек и повальных болезней
+fn inspect_sanitation(
+    world: &mut World,
+) -> Finding {
+    let overfilled = living_rooms_crowding(world)
+        .values()
+        .filter(|(capacity, occupied)| crowding(*occupied, *capacity) > 1.0)
+        .count();
+    let mut people_query = <&Satiety>::query();
+    let people = people_query.iter(world).count();
+    let mut sick_query = <&Sick>::query();
+    let sick = sick_query.iter(world).count();
+    Finding {
+        area: InspectionArea::Sanitation,
+        passed: overfilled == 0 && sick * 100 <= people * INSPECTION_SICK_SHARE,
+        note: format!("Переполненных жилячеек: {}, больных: {} из {}", overfilled, sick, people),
+    }
+}
+
+/// Все ли отряды укомплектованы
+fn inspect_squads(
+    world: &World,
+) -> Finding {
+    let squads = all_squads(world);
+    let incomplete = squads
+        .iter()
+        .filter(|(_, _, _, _, _, missing)| !missing.is_empty())
+        .count();
+    Finding {
+        area: InspectionArea::Squads,
+        passed: !squads.is_empty() && incomplete == 0,
+        note: format!("Отрядов: {}, неукомплектованных: {}", squads.len(), incomplete),
+    }
+}
+
+/// Имя комрада для акта
+fn comrad_name(
+    world: &World,
+    comrad: Entity,
+) -> String {
+    world
+        .entry_ref(comrad)
+        .ok()
+        .and_then(|entry| entry.get_component::<Name>().ok().cloned())
+        .map(|Name(name)| name)
+        .unwrap_or_default()
+}
+
+/// Провести проверку. Комиссия пересчитывает склады и закрывает учет.
+/// За каждое нарушение изымают часть ресурсов со складов и снимают доверие.
+/// За чистую проверку повышают лучшего функционера,
+/// а если нарушений много - арестовывают одного.
+pub fn inspect(
+    world: &mut World,
+    resources: &mut Resources,
+) -> InspectionReport {
+    let audit = close_books(world, resources);
+    let mut act = InspectionReport {
+        findings: vec![
+            inspect_storage(world, &audit),
+            inspect_sanitation(world),
+            inspect_squads(world),
+        ],
+        ..InspectionReport::default()
+    };
+    let violations = act.violations();
+
+    if violations > 0 {
+        for (res, amount) in what_we_have(world) {
+            let fine = RealUnits(amount.0 * INSPECTION_FINE * violations / 100);
+            if fine.0 > 0 {
+                let _ = writeoff(world, res, fine);
+                act.fines.insert(res, fine);
+            }
+        }
+        act.trust = -((violations * INSPECTION_PENALTY as usize) as i16);
+    } else {
+        act.trust = INSPECTION_REWARD as i16;
+    }
+
+    let staff = functionaries(world);
+    if violations == 0 {
+        let candidates: Vec<(Entity, Tier)> = staff
+            .iter()
+            .filter_map(|e| {
+                world
+                    .entry_ref(*e)
+                    .ok()
+                    .and_then(|entry| entry.get_component::<Tier>().ok().copied())
+                    .map(|tier| (*e, tier))
+            })
+            .filter(|(_, tier)| *tier < Tier::T3)
+            .collect();
+        if let Some((comrad, tier)) = candidates.iter().max_by_key(|(_, tier)| *tier).copied() {
+            let next = if tier == Tier::T1 { Tier::T2 } else { Tier::T3 };
+            if let Some(mut entry) = world.entry(comrad) {
+                entry.add_component(next);
+                entry.add_component(Experience(0));
+            }
+            record_history(world, comrad, format!("Повышен комиссией до {}", next));
+            act.promoted.push(comrad_name(world, comrad));
+        }
+    } else if violations >= INSPECTION_ARREST {
+        if let Some(comrad) = staff.choose(&mut thread_rng()).copied() {
+            act.arrested.push(comrad_name(world, comrad));
+            world.remove(comrad);
+        }
+    }
+
+    resources.insert(audit);
+    change_trust(resources, act.trust, "итоги партийной проверки");
+    for name in act.promoted.iter() {
+        report(resources, format!("Комиссия повысила функционера {}", name));
+    }
+    for name in act.arrested.iter() {
+        report(resources, format!("Комиссия арестовала функционера {}", name));
+    }
+    act
+}
+
+/// Отсчитать смену до прихода комиссии.
+/// При низком доверии комиссии приходят чаще.
+pub fn inspections_tick(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    let mut inspections = resources
+        .get::<Inspections>()
+        .map(|i| i.clone())
+        .unwrap_or_default();
+    let trust = resources
+        .get::<PartyTrust>()
+        .map(|t| *t)
+        .unwrap_or_default();
+    inspections.next_in = inspections
+        .next_in
+        .min(inspection_period(trust))
+        .saturating_sub(1);
+    if inspections.next_in == 0 {
+        report(resources, "Прибыла партийная комиссия".to_string());
+        inspections.last = Some(inspect(world, resources));
+        inspections.next_in = inspection_period(trust);
+    }
+    resources.insert(inspections);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::production::*;
+
+    #[test]
+    fn storekeepers_are_assigned_explicitly() {
+        let mut world = World::default();
+        let room = install_germ(&mut world, Germ::GermT1, AreaType::Living);
+        let junior = spawn_comrad(&mut world, Profession::Party, Tier::T1, room);
+        let senior = spawn_comrad(&mut world, Profession::Party, Tier::T2, room);
+        assert!(storekeepers(&world).is_empty());
+        assert_eq!(examiners_count(&world), 1);
+
+        assert_eq!(assign_storekeeper(&mut world), Ok(junior));
+        assert_eq!(storekeepers(&world), vec![junior]);
+        assert_eq!(functionaries(&world).len(), 2);
+        assert_eq!(examiners_count(&world), 1);
+
+        assert_eq!(assign_storekeeper(&mut world), Ok(senior));
+        assert_eq!(examiners_count(&world), 0);
+        assert_eq!(assign_storekeeper(&mut world), Err(SamosborError::ComradBusy));
+
+        assert!(dismiss_storekeeper(&mut world).is_some());
+        assert_eq!(storekeepers(&world).len(), 1);
+    }
+
+    #[test]
+    fn promotion_by_commission_resets_experience() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let barracks = install_germ(&mut world, Germ::GermT2, AreaType::Military);
+        spawn_1_g(&mut world, barracks);
+        let functionary = spawn_comrad(&mut world, Profession::Party, Tier::T1, barracks);
+        if let Some(mut entry) = world.entry(functionary) {
+            entry.add_component(Experience(500));
+        }
+        let act = inspect(&mut world, &mut resources);
+        assert_eq!(act.violations(), 0);
+        let entry = world.entry_ref(functionary).unwrap();
+        assert_eq!(*entry.get_component::<Tier>().unwrap(), Tier::T2);
+        assert_eq!(*entry.get_component::<Experience>().unwrap(), Experience(0));
+    }
+}
@@ -0,0 +1,414 @@
+use std::fmt;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use legion::*;
+
+use crate::area::*;
+use crate::core::*;
+use crate::people::*;
+use crate::production::*;
+use crate::resources::*;
+use crate::squad::*;
+use crate::storage::*;
+
+/// Штучные изделия. Изготавливаются на станках,
+/// лежат на складе, выдаются на руки.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Item {
+    Flamethrower, // Огнемет
+    Rake, // Грабли. Штатное оружие ликвидатора для ближнего боя.
+    ProtectiveSuit, // Защитный костюм
+    Toolkit, // Набор инструментов
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Item::Flamethrower   => write!(f, "Огнемет"),
+            Item::Rake           => write!(f, "Грабли"),
+            Item::ProtectiveSuit => write!(f, "Защитный костюм"),
+            Item::Toolkit        => write!(f, "Набор инструментов"),
+        }
+    }
+}
+
+/// FIXME: надо генерить список напрямую из энума.
+pub fn all_items() -> Vec<Item> {
+    vec![
+        Item::Flamethrower,
+        Item::Rake,
+        Item::ProtectiveSuit,
+        Item::Toolkit,
+    ]
+}
+
+/// Износ изделия. При нуле изделие разваливается.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Durability {
+    pub current: usize,
+    pub max: usize,
+}
+
+impl fmt::Display for Durability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.current, self.max)
+    }
+}
+
+/// Метка того, у кого на руках изделие
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EquippedBy(pub Entity);
+
+/// Сколько места на складе занимает изделие
+pub fn item_size(
+    item: Item,
+) -> AreaOccupied {
+    match item {
+        Item::Flamethrower => AreaOccupied(50),
+        Item::Rake => AreaOccupied(30),
+        Item::ProtectiveSuit => AreaOccupied(20),
+        Item::Toolkit => AreaOccupied(20),
+    }
+}
+
+/// Сколько смен использования выдерживает изделие
+pub fn item_durability(
+    item: Item,
+    tier: Tier,
+) -> Durability {
+    let base = match item {
+        Item::Flamethrower => 10,
+        Item::Rake => 30,
+        Item::ProtectiveSuit => 15,
+        Item::Toolkit => 60,
+    };
+    let max = match tier {
+        Tier::NoTier | Tier::T1 => base,
+        Tier::T2 => base * 2,
+        Tier::T3 => base * 4,
+    };
+    Durability { current: max, max }
+}
+
+/// На каком станке делается изделие данного разряда
+pub fn item_stationary(
+    item: Item,
+    tier: Tier,
+) -> Stationary {
+    match (item, tier) {
+        (Item::ProtectiveSuit, _) => Stationary::FormatFurnace,
+        (_, Tier::T3) => Stationary::BenchToolT3,
+        (_, Tier::T2) => Stationary::BenchToolT2,
+        (_, _) => Stationary::BenchToolT1,
+    }
+}
+
+/// Что надо по рабочим/оборудованию чтобы изготовить изделие
+pub fn item_requirements(
+    item: Item,
+    tier: Tier,
+) -> HashSet<TaskMeta> {
+    let bp = match item {
+        Item::Flamethrower => BuildPower(60),
+        Item::Rake => BuildPower(20),
+        Item::ProtectiveSuit => BuildPower(40),
+        Item::Toolkit => BuildPower(30),
+    };
+    [
+        TaskMeta {
+            prof: Profession::Worker,
+            tier,
+            bp,
+            stationary: item_stationary(item, tier),
+        },
+    ].iter().cloned().collect()
+}
+
+/// Что нужно по ресурсам чтобы изготовить изделие
+pub fn item_required_resources(
+    item: Item,
+    tier: Tier,
+) -> HashMap<Resource, RealUnits> {
+    let scrap = match tier {
+        Tier::NoTier | Tier::T1 => Resource::ScrapT1,
+        Tier::T2 => Resource::ScrapT2,
+        Tier::T3 => Resource::ScrapT3,
+    };
+    match item {
+        Item::Flamethrower => [
+            (scrap, RealUnits(3)),
+            (Resource::ComponentT1, RealUnits(2)),
+        ].iter().cloned().collect(),
+        Item::Rake => [
+            (scrap, RealUnits(2)),
+        ].iter().cloned().collect(),
+        Item::ProtectiveSuit => [
+            (Resource::Polymer, RealUnits(3)),
+        ].iter().cloned().collect(),
+        Item::Toolkit => [
+            (scrap, RealUnits(2)),
+            (Resource::ComponentT1, RealUnits(1)),
+        ].iter().cloned().collect(),
+    }
+}
+
+/// Можем ли мы начать изготовление изделия.
+/// Ok - склад, куда изделие ляжет по готовности.
+pub fn can_make_item(
+    world: &mut World,
+    exist_rsrcs: HashMap<Resource, RealUnits>,
+    item: Item,
+    tier: Tier,
+) -> Result<Entity, Box<Shortage>> {
+    let mut shortage = missing_requirements(
+        world,
+        exist_rsrcs,
+        item_requirements(item, tier),
+        item_required_resources(item, tier),
+    );
+    let room = get_sufficent_room(
+        world,
+        item_size(item),
+        AreaType::Party,
+    );
+    shortage.no_room = room.is_none();
+    match room {
+        Some(room) if shortage.is_empty() => Ok(room),
+        _ => Err(Box::new(shortage)),
+    }
+}
+
+/// Запустить изготовление изделия.
+/// Предполагается что возможность изготовления была проверена ранее
+pub fn start_item_task(
+    world: &mut World,
+    item: Item,
+    tier: Tier,
+    room: Entity,
+) -> Result<Entity, SamosborError> {
+    writeoff_bunch(world, item_required_resources(item, tier))?;
+    Ok(world.push((
+        item,
+        tier,
+        item_durability(item, tier),
+        item_size(item),
+        TaskStatus::Constructing,
+        task_meta2progress(item_requirements(item, tier)),
+        BelongsToRoom(room),
+    )))
+}
+
+/// Какие изделия положены комраду по его профессии и роли в отряде
+pub fn wanted_items(
+    prof: Profession,
+    role: Option<SquadRole>,
+) -> Vec<Item> {
+    match (prof, role) {
+        (Profession::Likvidator, Some(SquadRole::Sergeant)) |
+        (Profession::Likvidator, Some(SquadRole::Flamethrower)) =>
+            vec![Item::ProtectiveSuit, Item::Flamethrower],
+        (Profession::Likvidator, Some(SquadRole::Raker)) =>
+            vec![Item::ProtectiveSuit, Item::Rake],
+        (Profession::Likvidator, _) => vec![Item::ProtectiveSuit],
+        (Profession::Worker, _) => vec![Item::Toolkit],
+        _ => Vec::new(),
+    }
+}
+
+/// Что у комрада на руках
+pub fn equipped_items(
+    world: &World,
+) -> HashMap<Entity, Vec<(Entity, Item, Tier, Durability)>> {
+    let mut result: HashMap<Entity, Vec<(Entity, Item, Tier, Durability)>> = HashMap::new();
+    let mut query = <(Entity, &Item, &Tier, &Durability, &EquippedBy)>::query();
+    for (entity, item, tier, durability, EquippedBy(owner)) in query.iter(world) {
+        result
+            .entry(*owner)
+            .or_default()
+            .push((*entity, *item, *tier, *durability));
+    }
+    result
+}
+
+/// Выдать изделие комраду. Изделие освобождает место на складе.
+pub fn equip_item(
+    world: &mut World,
+    item: Entity,
+    comrad: Entity,
+) {
+    if let Some(mut entry) = world.entry(item) {
+        entry.remove_component::<BelongsToRoom>();
+        entry.add_component(EquippedBy(comrad));
+    }
+}
+
+/// Вернуть изделие на склад. Если места нет - изделие теряется.
+pub fn unequip_item(
+    world: &mut World,
+    item: Entity,
+    size: AreaOccupied,
+) {
+    match get_sufficent_room(world, size, AreaType::Party) {
+        Some(room) => {
+            if let Some(mut entry) = world.entry(item) {
+                entry.remove_component::<EquippedBy>();
+                entry.add_component(BelongsToRoom(room));
+            }
+        },
+        None => {
+            world.remove(item);
+        },
+    }
+}
+
+/// Раздать готовые изделия со склада тем, кому они положены.
+/// Изделия выбывших комрадов возвращаются на склад.
+pub fn distribute_items(
+    world: &mut World,
+) {
+    let mut orphans = Vec::new();
+    let mut orphans_query = <(Entity, &Item, &EquippedBy)>::query();
+    for (entity, item, EquippedBy(owner)) in orphans_query.iter(world) {
+        if !world.contains(*owner) {
+            orphans.push((*entity, item_size(*item)));
+        }
+    }
+    for (entity, size) in orphans {
+        unequip_item(world, entity, size);
+    }
+
+    // Свободные изделия на складе, лучшие в начале
+    let mut stock: Vec<(Entity, Item, Tier)> = Vec::new();
+    let mut stock_query = <(Entity, &Item, &Tier, &TaskStatus, &BelongsToRoom)>::query();
+    for (entity, item, tier, status, _) in stock_query.iter(world) {
+        if *status == TaskStatus::Ready {
+            stock.push((*entity, *item, *tier));
+        }
+    }
+    stock.sort_by_key(|(_, _, tier)| std::cmp::Reverse(*tier));
+
+    let equipped = equipped_items(world);
+    let mut needs: Vec<(Entity, Item)> = Vec::new();
+    let mut people_query = <(Entity, &Profession, Option<&InSquad>)>::query();
+    for (entity, prof, in_squad) in people_query.iter(world) {
+        let has: HashSet<Item> = equipped
+            .get(entity)
+            .map(|v| v.iter().map(|(_, item, _, _)| *item).collect())
+            .unwrap_or_default();
+        for item in wanted_items(*prof, in_squad.map(|s| s.role)) {
+            if !has.contains(&item) {
+                needs.push((*entity, item));
+            }
+        }
+    }
+
+    for (comrad, item) in needs {
+        if let Some(pos) = stock.iter().position(|(_, i, _)| *i == item) {
+            let (item_entity, _, _) = stock.remove(pos);
+            equip_item(world, item_entity, comrad);
+        }
+    }
+}
+
+/// Изделия на руках у этих комрадов изнашиваются на единицу.
+/// Развалившиеся изделия выбрасываются.
+pub fn wear_items(
+    world: &mut World,
+    wearers: &HashSet<Entity>,
+    kinds: &[Item],
+) {
+    let mut broken = Vec::new();
+    let mut query = <(Entity, &Item, &mut Durability, &EquippedBy)>::query();
+    for (entity, item, durability, EquippedBy(owner)) in query.iter_mut(world) {
+        if wearers.contains(owner) && kinds.contains(item) {
+            durability.current = durability.current.saturating_sub(1);
+            if durability.current == 0 {
+                broken.push(*entity);
+            }
+        }
+    }
+    for entity in broken {
+        world.remove(entity);
+    }
+}
+
+/// Во сколько раз инструмент увеличивает выработку рабочего
+pub fn toolkit_coef(
+    tier: Tier,
+) -> f32 {
+    match tier {
+        Tier::NoTier => 1.0,
+        Tier::T1 => 1.25,
+        Tier::T2 => 1.5,
+        Tier::T3 => 2.0,
+    }
+}
+
+/// Боевая мощь одного бойца с учетом снаряжения
+pub fn combat_power(
+    tier: Tier,
+    items: &[(Entity, Item, Tier, Durability)],
+) -> usize {
+    let base = match tier {
+        Tier::NoTier => 0,
+        Tier::T1 => 10,
+        Tier::T2 => 20,
+        Tier::T3 => 40,
+    };
+    items
+        .iter()
+        .fold(base, |acc, (_, item, item_tier, _)| {
+            let bonus = match item {
+                Item::Flamethrower => 20,
+                Item::Rake => 10,
+                Item::ProtectiveSuit => 5,
+                Item::Toolkit => 0,
+            };
+            acc + bonus * (*item_tier as usize)
+        })
+}
+
+/// Боевая мощь отряда
+pub fn squad_combat_power(
+    world: &World,
+    squad: Entity,
+) -> usize {
+    let equipped = equipped_items(world);
+    let empty = Vec::new();
+    squad_members(world, squad)
+        .iter()
+        .map(|(_, tier, entity)| {
+            combat_power(*tier, equipped.get(entity).unwrap_or(&empty))
+        })
+        .sum()
+}
+
+/// Сколько каких изделий лежит на складе: (изделие, разряд) -> количество
+pub fn items_in_stock(
+    world: &World,
+) -> HashMap<(Item, Tier), usize> {
+    let mut result = HashMap::new();
+    let mut query = <(&Item, &Tier, &TaskStatus, &BelongsToRoom)>::query();
+    for (item, tier, status, _) in query.iter(world) {
+        if *status == TaskStatus::Ready {
+            *result.entry((*item, *tier)).or_insert(0) += 1;
+        }
+    }
+    result
+}
+
+/// Что изготавливается сейчас, и какой прогресс
+pub fn items_in_progress(
+    world: &World,
+) -> Vec<(Item, Tier, TaskProgress)> {
+    let mut result = Vec::new();
+    let mut query = <(&Item, &Tier, &TaskProgress)>::query();
+    for (item, tier, progress) in query.iter(world) {
+        result.push((*item, *tier, progress.clone()));
+    }
+    result.sort();
+    result
+}
@@ -9,10 +9,32 @@ mod storage;
 mod people;
 mod area;
 mod turn;
+mod squad;
+mod items;
+mod recipes;
+mod mood;
+mod unrest;
+mod housing;
+mod names;
+mod experience;
+mod education;
+mod population;
+mod refugees;
+mod trust;
+mod quota;
+mod inspection;
+mod accounting;
+mod coupons;
+mod research;
+mod content;
+mod lint;
 mod assets;
 mod queries;
 
 pub use app::GlavblockApp;
+pub use content::CONTENT_PATH;
+pub use research::TECH_TREE_PATH;
+pub use lint::lint_files;
 
 #[cfg(target_arch = "wasm32")]
 use console_error_panic_hook;
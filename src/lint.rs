@@ -0,0 +1,278 @@
+use std::collections::HashSet;
+
+use crate::area::*;
+use crate::content::*;
+use crate::core::*;
+use crate::people::*;
+use crate::production::*;
+use crate::quota::*;
+use crate::recipes::*;
+use crate::research::*;
+use crate::resources::*;
+use crate::storage::*;
+
+/// Что блок получает не из своих техпроцессов: кроме стартового запаса
+/// концентрат привозят от Главблока за план и от треста по снабжению
+static DELIVERED_RESOURCES: &[Resource] = &[
+    Resource::Concentrat,
+];
+
+/// Проверить файлы контента и дерева технологий.
+/// Возвращает диагностику, пустой список - все в порядке.
+pub fn lint_files(
+    content_path: &str,
+    tech_tree_path: &str,
+) -> Vec<String> {
+    let content_text = match std::fs::read_to_string(content_path) {
+        Ok(text) => text,
+        Err(e) => return vec![format!("{}: не удалось прочитать: {}", content_path, e)],
+    };
+    let tree_text = match std::fs::read_to_string(tech_tree_path) {
+        Ok(text) => text,
+        Err(e) => return vec![format!("{}: не удалось прочитать: {}", tech_tree_path, e)],
+    };
+    let mut errors = Vec::new();
+    let content = match parse_content(&content_text) {
+        Ok(content) => Some(content),
+        Err(e) => {
+            errors.push(format!("{}: {}", content_path, e));
+            None
+        },
+    };
+    let tree = match parse_tech_tree(&tree_text) {
+        Ok(tree) => Some(tree),
+        Err(e) => {
+            errors.push(format!("{}: {}", tech_tree_path, e));
+            None
+        },
+    };
+    if let Some(content) = content.as_ref() {
+        errors.extend(
+            lint_content(content)
+                .into_iter()
+                .map(|e| format!("{}: {}", content_path, e))
+        );
+    }
+    if let Some(tree) = tree.as_ref() {
+        errors.extend(
+            validate_tech_tree(tree)
+                .into_iter()
+                .chain(lint_labor(
+                    "research",
+                    tree.0.iter().flat_map(research_requirements),
+                ))
+                .map(|e| format!("{}: {}", tech_tree_path, e))
+        );
+    }
+    errors.extend(
+        lint_recipes()
+            .into_iter()
+            .map(|e| format!("recipes.rs: {}", e))
+    );
+    errors.extend(
+        lint_quota()
+            .into_iter()
+            .map(|e| format!("quota.rs: {}", e))
+    );
+    errors
+}
+
+/// Проверить контент: полнота, исполнители и размеры
+pub fn lint_content(
+    content: &Content,
+) -> Vec<String> {
+    let mut errors = validate_content(content);
+    for stationary in all_stationaries() {
+        if let Some(def) = content.stationaries.get(&stationary) {
+            errors.extend(lint_labor(
+                &format!("stationaries.{:?}", stationary),
+                def.labor.iter().map(LaborDef::task_meta),
+            ));
+        }
+    }
+    for (germ, def) in content.germs.iter() {
+        errors.extend(lint_labor(
+            &format!("germs.{:?}", germ),
+            def.labor.iter().map(LaborDef::task_meta),
+        ));
+    }
+    // ресурсы хранятся в партпомещениях, единица ресурса должна влезать
+    // в самую маленькую герму, которую можно поставить под склад
+    if let Some((smallest, capacity)) = content.germs
+        .iter()
+        .filter(|(germ, _)| germ_purposes(**germ).contains(&AreaType::Party))
+        .map(|(germ, def)| (*germ, def.capacity))
+        .min_by_key(|(_, capacity)| *capacity)
+    {
+        for (resource, piece) in content.pieces.iter() {
+            if *piece > capacity {
+                errors.push(format!(
+                    "pieces.{:?}: {} не влезает в самый маленький склад {:?} ({})",
+                    resource,
+                    piece,
+                    smallest,
+                    capacity,
+                ));
+            }
+        }
+    }
+    errors
+}
+
+/// Проверить, что работу есть кому делать: профессия и разряд,
+/// которые бывают у комрадов в колонии
+fn lint_labor(
+    place: &str,
+    labor: impl Iterator<Item = TaskMeta>,
+) -> Vec<String> {
+    labor
+        .filter(|meta| meta.prof == Profession::NoProf || meta.tier == Tier::NoTier)
+        .map(|meta| format!(
+            "{}: работу поручили {:?} {:?}, таких работников не бывает",
+            place,
+            meta.prof,
+            meta.tier,
+        ))
+        .collect()
+}
+
+/// Что блок может заполучить: стартовый запас, поставки снаружи
+/// и все, что производят техпроцессы из достижимого сырья
+fn reachable_resources() -> HashSet<Resource> {
+    let mut reachable: HashSet<Resource> = INITIAL_STOCK
+        .iter()
+        .map(|(res, _)| *res)
+        .chain(DELIVERED_RESOURCES.iter().cloned())
+        .collect();
+    loop {
+        let before = reachable.len();
+        for recipe in all_recipes() {
+            if recipe_inputs(recipe).keys().all(|res| reachable.contains(res)) {
+                for res in recipe_outputs(recipe).keys() {
+                    reachable.insert(*res);
+                }
+            }
+        }
+        if reachable.len() == before {
+            break;
+        }
+    }
+    reachable
+}
+
+/// Проверить техпроцессы: исполнители и достижимость сырья
+pub fn lint_recipes() -> Vec<String> {
+    let mut errors = Vec::new();
+    for recipe in all_recipes() {
+        if recipe_outputs(recipe).is_empty() {
+            errors.push(format!("{:?}: ничего не производит", recipe));
+        }
+        errors.extend(lint_labor(
+            &format!("{:?}", recipe),
+            recipe_requirements(recipe).into_iter(),
+        ));
+    }
+
+    let reachable = reachable_resources();
+    for recipe in all_recipes() {
+        let mut unreachable: Vec<String> = recipe_inputs(recipe)
+            .keys()
+            .filter(|res| !reachable.contains(res))
+            .map(|res| format!("{:?}", res))
+            .collect();
+        if !unreachable.is_empty() {
+            unreachable.sort();
+            errors.push(format!(
+                "{:?}: сырье негде взять: {}",
+                recipe,
+                unreachable.join(", "),
+            ));
+        }
+    }
+    errors
+}
+
+/// Проверить план Главблока: требовать можно только то, что блок может заполучить
+pub fn lint_quota() -> Vec<String> {
+    let reachable = reachable_resources();
+    QUOTA_CHOICES
+        .iter()
+        .filter(|(res, _)| !reachable.contains(res))
+        .map(|(res, _)| format!("QUOTA_CHOICES: {:?} негде взять", res))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CONTENT_TEXT: &str = include_str!("../data/content.ron");
+
+    #[test]
+    fn broken_tech_tree_is_reported() {
+        let text = r#"[
+            (
+                id: "orphan",
+                name: "Сирота",
+                description: "",
+                requires: ["nowhere"],
+                lab: BenchToolT1,
+                tier: T1,
+                bp: 0,
+                reagents: {},
+                unlocks: [],
+            ),
+            (
+                id: "egg",
+                name: "Яйцо",
+                description: "",
+                requires: ["chicken"],
+                lab: LabT1,
+                tier: T1,
+                bp: 10,
+                reagents: {},
+                unlocks: [SamosborForecast],
+            ),
+            (
+                id: "chicken",
+                name: "Курица",
+                description: "",
+                requires: ["egg"],
+                lab: LabT1,
+                tier: T1,
+                bp: 10,
+                reagents: {},
+                unlocks: [SamosborForecast],
+            ),
+        ]"#;
+        let tree = parse_tech_tree(text).unwrap();
+        let errors = validate_tech_tree(&tree);
+        assert!(errors.contains(&"orphan: требует неизвестное исследование nowhere".to_string()));
+        assert!(errors.contains(&"orphan: lab должен быть лабораторией, а не BenchToolT1".to_string()));
+        assert!(errors.contains(&"orphan: bp должен быть больше 0".to_string()));
+        assert!(errors.contains(&"orphan: исследование ничего не открывает".to_string()));
+        assert!(errors.contains(&"egg: недостижимо, цикл в требованиях".to_string()));
+        assert!(errors.contains(&"chicken: недостижимо, цикл в требованиях".to_string()));
+        assert!(parse_tech_tree("[(id: \"oops\",").is_err());
+    }
+
+    #[test]
+    fn broken_content_is_reported() {
+        assert!(lint_content(&parse_content(CONTENT_TEXT).unwrap()).is_empty());
+        // в склад на T2 герме лом уже не влезает, а в T1 склад не ставят
+        let text = CONTENT_TEXT.replacen("ScrapT1: 100,", "ScrapT1: 20000,", 1);
+        let errors = lint_content(&parse_content(&text).unwrap());
+        assert_eq!(
+            errors,
+            vec!["pieces.ScrapT1: 20000 не влезает в самый маленький склад GermT2 (15000)".to_string()],
+        );
+        let text = CONTENT_TEXT.replacen("ScrapT1: 100,", "ScrapT1: 2000,", 1);
+        assert!(lint_content(&parse_content(&text).unwrap()).is_empty());
+        assert!(parse_content("(stationaries: {").is_err());
+    }
+
+    #[test]
+    fn quota_demands_reachable_resources() {
+        assert!(lint_quota().is_empty());
+    }
+}
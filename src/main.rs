@@ -7,6 +7,24 @@ mod storage;
 mod people;
 mod area;
 mod turn;
+mod squad;
+mod items;
+mod recipes;
+mod mood;
+mod unrest;
+mod housing;
+mod names;
+mod experience;
+mod education;
+mod population;
+mod refugees;
+mod trust;
+mod quota;
+mod inspection;
+mod accounting;
+mod coupons;
+mod research;
+mod content;
 
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
@@ -0,0 +1,360 @@
+use std::fmt;
+use std::collections::HashMap;
+
+use legion::*;
+
+use crate::area::*;
+use crate::core::*;
+use crate::people::*;
+use crate::production::*;
+use crate::unrest::*;
+use crate::housing::*;
+use crate::refugees::*;
+use crate::coupons::*;
+use crate::inspection::*;
+
+/// Из чего складывается настроение комрада
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MoodFactor {
+    Hunger, // Голод или обжорство
+    Crowding, // Теснота в помещении
+    Deaths, // Недавние смерти в блоке
+    Leisure, // Досуг: нейротерминалы
+    Propaganda, // Работа партийных функционеров
+    Repression, // Подавление волнений
+    Sickness, // Болезнь
+    Refugees, // Блок не пустил беженцев
+    Samosbor, // Страх после самосбора
+    Wage, // Оплата в талонах
+    Purchases, // Покупки на талоны
+}
+
+impl fmt::Display for MoodFactor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoodFactor::Hunger     => write!(f, "Питание"),
+            MoodFactor::Crowding   => write!(f, "Теснота"),
+            MoodFactor::Deaths     => write!(f, "Смерти товарищей"),
+            MoodFactor::Leisure    => write!(f, "Досуг"),
+            MoodFactor::Propaganda => write!(f, "Агитация"),
+            MoodFactor::Repression => write!(f, "Подавление волнений"),
+            MoodFactor::Sickness   => write!(f, "Болезнь"),
+            MoodFactor::Refugees   => write!(f, "Отказ беженцам"),
+            MoodFactor::Samosbor   => write!(f, "Самосбор"),
+            MoodFactor::Wage       => write!(f, "Зарплата"),
+            MoodFactor::Purchases  => write!(f, "Покупки"),
+        }
+    }
+}
+
+/// Вклад каждого фактора в настроение комрада
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct MoodFactors(pub HashMap<MoodFactor, i8>);
+
+/// Настроение без всяких факторов. 5 - нейтрал.
+pub static MOOD_BASE: i8 = 5;
+
+/// Сколько людей обслуживает один нейротерминал
+pub static PEOPLE_PER_TERMINAL: usize = 25;
+
+/// Сколько людей может охватить агитацией один партийный функционер
+pub static PEOPLE_PER_AGITATOR: usize = 20;
+
+/// Сколько недавних смертей в блоке. Забываются постепенно.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct RecentDeaths(pub usize);
+
+/// Сколько смен еще держится страх после самосбора
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct SamosborFear(pub usize);
+
+impl MoodFactors {
+    /// Итоговое настроение: база плюс все факторы, в пределах 0..=10
+    pub fn mood(&self) -> Mood {
+        let mut mood = Mood(MOOD_BASE as u8);
+        mood.change(self.0.values().fold(0i8, |acc, v| acc.saturating_add(*v)));
+        mood
+    }
+
+    /// Факторы для отображения, без нулевых
+    pub fn breakdown(&self) -> Vec<(MoodFactor, i8)> {
+        let mut result: Vec<(MoodFactor, i8)> = self.0
+            .iter()
+            .filter(|(_, v)| **v != 0)
+            .map(|(k, v)| (*k, *v))
+            .collect();
+        result.sort();
+        result
+    }
+}
+
+/// Как сытость влияет на настроение
+pub fn hunger_mood_factor(
+    sat: Satiety,
+) -> i8 {
+    if sat.0 < SATIETY_HUNGER / 2 {
+        -3
+    } else if sat.0 < SATIETY_HUNGER {
+        -2
+    } else if sat.0 > SATIETY_OBESITY {
+        -1
+    } else if sat.0 >= SATIETY_FULL - 20 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Как заполненность жилячейки влияет на настроение
+pub fn crowding_mood_factor(
+    occupied: AreaOccupied,
+    capacity: AreaCapacity,
+) -> i8 {
+    let ratio = crowding(occupied, capacity);
+    if ratio > 1.0 {
+        -2
+    } else if ratio >= 0.9 {
+        -1
+    } else if ratio < 0.5 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Как болезнь влияет на настроение
+pub fn sickness_mood_factor(
+    sick: Option<&Sick>,
+) -> i8 {
+    if sick.is_some() { -2 } else { 0 }
+}
+
+/// Как недавние смерти влияют на настроение
+pub fn deaths_mood_factor(
+    deaths: RecentDeaths,
+) -> i8 {
+    -((deaths.0 / 5).min(3) as i8)
+}
+
+/// Как страх после самосбора влияет на настроение
+pub fn samosbor_mood_factor(
+    fear: SamosborFear,
+) -> i8 {
+    if fear.0 > 0 { -2 } else { 0 }
+}
+
+/// Хватает ли нейротерминалов на всех
+pub fn leisure_mood_factor(
+    terminals: usize,
+    people: usize,
+) -> i8 {
+    if people > 0 && terminals * PEOPLE_PER_TERMINAL >= people { 1 } else { 0 }
+}
+
+/// Хватает ли агитаторов на всех
+pub fn propaganda_mood_factor(
+    agitators: usize,
+    people: usize,
+) -> i8 {
+    let covered = agitators * PEOPLE_PER_AGITATOR;
+    if people == 0 {
+        0
+    } else if covered >= people * 2 {
+        2
+    } else if covered >= people {
+        1
+    } else {
+        0
+    }
+}
+
+/// Пересчитать настроение всех комрадов из факторов.
+/// Недавние смерти и страх самосбора понемногу забываются.
+pub fn mood_tick(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    let deaths = resources
+        .get::<RecentDeaths>()
+        .map(|d| *d)
+        .unwrap_or_default();
+    let unrest = resources
+        .get::<Unrest>()
+        .map(|u| *u)
+        .unwrap_or_default();
+    let fear = resources
+        .get::<SamosborFear>()
+        .map(|f| *f)
+        .unwrap_or_default();
+    let refugees = resources
+        .get::<RefugeeRecord>()
+        .map(|r| *r)
+        .unwrap_or_default();
+
+    let rooms = living_rooms_crowding(world);
+
+    let mut terminals_query = <(&Stationary, &TaskStatus)>::query();
+    let terminals = terminals_query
+        .iter(world)
+        .filter(|(s, status)| **s == Stationary::NeuroTerminal && **status == TaskStatus::Ready)
+        .count();
+    let mut people_query = <&Profession>::query();
+    let people = people_query.iter(world).count();
+    // кладовщики сидят на складах и не агитируют
+    let mut agitators_query = <&Profession>::query()
+        .filter(!component::<Storekeeper>());
+    let agitators = agitators_query
+        .iter(world)
+        .filter(|p| **p == Profession::Party)
+        .count();
+
+    let leisure = leisure_mood_factor(terminals, people);
+    let propaganda = propaganda_mood_factor(agitators, people);
+    let deaths_factor = deaths_mood_factor(deaths);
+    let repression = repression_mood_factor(unrest, suppressors_count(world));
+    let refugees = refugees_mood_factor(refugees);
+    let samosbor = samosbor_mood_factor(fear);
+
+    let mut query = <(
+        &Satiety,
+        &BelongsToRoom,
+        &mut MoodFactors,
+        &mut Mood,
+        Option<&Sick>,
+        Option<&Wallet>,
+    )>::query();
+    for (sat, BelongsToRoom(room), factors, mood, sick, wallet) in query.iter_mut(world) {
+        // теснота считается только в жилячейках
+        let crowding = rooms
+            .get(room)
+            .map(|(capacity, occupied)| crowding_mood_factor(*occupied, *capacity))
+            .unwrap_or(0);
+        factors.0.insert(MoodFactor::Hunger, hunger_mood_factor(*sat));
+        factors.0.insert(MoodFactor::Crowding, crowding);
+        factors.0.insert(MoodFactor::Deaths, deaths_factor);
+        factors.0.insert(MoodFactor::Leisure, leisure);
+        factors.0.insert(MoodFactor::Propaganda, propaganda);
+        factors.0.insert(MoodFactor::Repression, repression);
+        factors.0.insert(MoodFactor::Sickness, sickness_mood_factor(sick));
+        factors.0.insert(MoodFactor::Refugees, refugees);
+        factors.0.insert(MoodFactor::Samosbor, samosbor);
+        factors.0.insert(MoodFactor::Wage, wage_mood_factor(wallet));
+        factors.0.insert(MoodFactor::Purchases, purchases_mood_factor(wallet));
+        *mood = factors.mood();
+    }
+
+    resources.insert(RecentDeaths(deaths.0 * 9 / 10));
+    resources.insert(SamosborFear(fear.0.saturating_sub(1)));
+}
+
+/// Записать смерти в память блока
+pub fn remember_deaths(
+    resources: &mut Resources,
+    count: usize,
+) {
+    let deaths = resources
+        .get::<RecentDeaths>()
+        .map(|d| *d)
+        .unwrap_or_default();
+    resources.insert(RecentDeaths(deaths.0 + count));
+}
+
+/// Из чего сложилось настроение конкретного комрада
+pub fn mood_breakdown(
+    world: &World,
+    comrad: Entity,
+) -> Vec<(MoodFactor, i8)> {
+    world
+        .entry_ref(comrad)
+        .ok()
+        .and_then(|entry| entry.get_component::<MoodFactors>().ok().map(|f| f.breakdown()))
+        .unwrap_or_default()
+}
+
+/// Ниже этого настроения комрад отказывается работать
+pub static MOOD_REFUSE_WORK: u8 = 2;
+
+/// Во сколько раз настроение и сытость меняют выработку комрада.
+/// Несчастный работает вполсилы, счастливый - с огоньком,
+/// совсем отчаявшийся не работает вовсе. Голод дополнительно
+/// снижает выработку на четверть.
+pub fn morale_coef(
+    mood: Mood,
+    sat: Satiety,
+) -> f32 {
+    let by_mood = match mood.0 {
+        m if m < MOOD_REFUSE_WORK => 0.0,
+        2..=3 => 0.5,
+        4..=7 => 1.0,
+        _ => 1.2,
+    };
+    let by_satiety = if sat.0 < SATIETY_HUNGER { 0.75 } else { 1.0 };
+    by_mood * by_satiety
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn factors(list: &[(MoodFactor, i8)]) -> MoodFactors {
+        MoodFactors(list.iter().cloned().collect())
+    }
+
+    #[test]
+    fn mood_is_base_plus_factors_within_bounds() {
+        assert_eq!(factors(&[]).mood(), Mood(MOOD_BASE as u8));
+        assert_eq!(factors(&[(MoodFactor::Hunger, -2), (MoodFactor::Leisure, 1)]).mood(), Mood(4));
+        assert_eq!(factors(&[(MoodFactor::Hunger, -3), (MoodFactor::Deaths, -3)]).mood(), Mood(0));
+        assert_eq!(factors(&[(MoodFactor::Propaganda, 4), (MoodFactor::Hunger, 1), (MoodFactor::Leisure, 1)]).mood(), Mood(MOOD_MAX));
+    }
+
+    #[test]
+    fn breakdown_skips_zero_factors() {
+        let breakdown = factors(&[
+            (MoodFactor::Leisure, 1),
+            (MoodFactor::Hunger, -2),
+            (MoodFactor::Crowding, 0),
+        ]).breakdown();
+        assert_eq!(breakdown, vec![(MoodFactor::Hunger, -2), (MoodFactor::Leisure, 1)]);
+    }
+
+    #[test]
+    fn deaths_weigh_in_steps_of_five() {
+        assert_eq!(deaths_mood_factor(RecentDeaths(0)), 0);
+        assert_eq!(deaths_mood_factor(RecentDeaths(4)), 0);
+        assert_eq!(deaths_mood_factor(RecentDeaths(5)), -1);
+        assert_eq!(deaths_mood_factor(RecentDeaths(100)), -3);
+    }
+
+    #[test]
+    fn leisure_and_propaganda_need_coverage() {
+        assert_eq!(leisure_mood_factor(0, 0), 0);
+        assert_eq!(leisure_mood_factor(1, PEOPLE_PER_TERMINAL), 1);
+        assert_eq!(leisure_mood_factor(1, PEOPLE_PER_TERMINAL + 1), 0);
+        assert_eq!(propaganda_mood_factor(5, 0), 0);
+        assert_eq!(propaganda_mood_factor(1, PEOPLE_PER_AGITATOR + 1), 0);
+        assert_eq!(propaganda_mood_factor(1, PEOPLE_PER_AGITATOR), 1);
+        assert_eq!(propaganda_mood_factor(2, PEOPLE_PER_AGITATOR), 2);
+    }
+
+    #[test]
+    fn morale_thresholds() {
+        let fed = Satiety(SATIETY_FULL);
+        assert_eq!(morale_coef(Mood(0), fed), 0.0);
+        assert_eq!(morale_coef(Mood(MOOD_REFUSE_WORK - 1), fed), 0.0);
+        assert_eq!(morale_coef(Mood(MOOD_REFUSE_WORK), fed), 0.5);
+        assert_eq!(morale_coef(Mood(3), fed), 0.5);
+        assert_eq!(morale_coef(Mood(4), fed), 1.0);
+        assert_eq!(morale_coef(Mood(7), fed), 1.0);
+        assert_eq!(morale_coef(Mood(8), fed), 1.2);
+        assert_eq!(morale_coef(Mood(MOOD_MAX), fed), 1.2);
+    }
+
+    #[test]
+    fn hunger_cuts_morale_by_quarter() {
+        let hungry = Satiety(SATIETY_HUNGER - 1);
+        assert_eq!(morale_coef(Mood(5), hungry), 0.75);
+        assert_eq!(morale_coef(Mood(5), Satiety(SATIETY_HUNGER)), 1.0);
+        assert_eq!(morale_coef(Mood(MOOD_REFUSE_WORK - 1), hungry), 0.0);
+    }
+}
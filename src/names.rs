@@ -0,0 +1,108 @@
+use rand::thread_rng;
+use rand::seq::SliceRandom;
+
+use crate::people::*;
+
+static MALE_NAMES: &[&str] = &[
+    "Алексей", "Борис", "Василий", "Геннадий", "Дмитрий", "Евгений",
+    "Иван", "Константин", "Леонид", "Михаил", "Николай", "Олег",
+    "Павел", "Роман", "Сергей", "Тимофей", "Федор", "Юрий",
+];
+
+static FEMALE_NAMES: &[&str] = &[
+    "Анна", "Валентина", "Галина", "Дарья", "Екатерина", "Зинаида",
+    "Ирина", "Клавдия", "Людмила", "Мария", "Надежда", "Ольга",
+    "Раиса", "Светлана", "Тамара", "Ульяна", "Вера", "Лидия",
+];
+
+/// Мужские имена, от которых образуется отчество
+static FATHER_NAMES: &[(&str, &str)] = &[
+    ("Алексеевич", "Алексеевна"),
+    ("Борисович", "Борисовна"),
+    ("Васильевич", "Васильевна"),
+    ("Дмитриевич", "Дмитриевна"),
+    ("Иванович", "Ивановна"),
+    ("Михайлович", "Михайловна"),
+    ("Николаевич", "Николаевна"),
+    ("Петрович", "Петровна"),
+    ("Сергеевич", "Сергеевна"),
+    ("Федорович", "Федоровна"),
+];
+
+/// Фамилии в мужском и женском роде
+static SURNAMES: &[(&str, &str)] = &[
+    ("Иванов", "Иванова"),
+    ("Кузнецов", "Кузнецова"),
+    ("Смирнов", "Смирнова"),
+    ("Попов", "Попова"),
+    ("Волков", "Волкова"),
+    ("Морозов", "Морозова"),
+    ("Зайцев", "Зайцева"),
+    ("Соловьев", "Соловьева"),
+    ("Лебедев", "Лебедева"),
+    ("Козлов", "Козлова"),
+    ("Новиков", "Новикова"),
+    ("Герметичный", "Герметичная"),
+    ("Бетонов", "Бетонова"),
+    ("Заводской", "Заводская"),
+];
+
+static ORIGINS: &[(&str, &str)] = &[
+    ("Родился в блоке", "Родилась в блоке"),
+    ("Переведен из соседнего блока", "Переведена из соседнего блока"),
+    ("Найден мусорщиками в заброшенном секторе", "Найдена мусорщиками в заброшенном секторе"),
+    ("Прибыл по распределению", "Прибыла по распределению"),
+    ("Выжил после самосбора в родном блоке", "Выжила после самосбора в родном блоке"),
+];
+
+static TRAITS: &[(&str, &str)] = &[
+    ("исполнителен", "исполнительна"),
+    ("молчалив", "молчалива"),
+    ("любит поспорить на партсобраниях", "любит поспорить на партсобраниях"),
+    ("боится самосбора больше других", "боится самосбора больше других"),
+    ("хорошо отзывается о руководстве", "хорошо отзывается о руководстве"),
+    ("замечен в мелких хищениях, не доказано", "замечена в мелких хищениях, не доказано"),
+    ("мастер на все руки", "мастерица на все руки"),
+    ("плохо переносит тесноту", "плохо переносит тесноту"),
+];
+
+fn pick<'a>(list: &[&'a str]) -> &'a str {
+    list.choose(&mut thread_rng()).unwrap()
+}
+
+fn pick_pair(list: &[(&str, &str)], sex: Sex) -> String {
+    let (male, female) = list.choose(&mut thread_rng()).unwrap();
+    match sex {
+        Sex::Male => male.to_string(),
+        Sex::Female => female.to_string(),
+    }
+}
+
+/// Сгенерировать ФИО
+pub fn random_name(
+    sex: Sex,
+) -> Name {
+    let first = match sex {
+        Sex::Male => pick(MALE_NAMES),
+        Sex::Female => pick(FEMALE_NAMES),
+    };
+    Name(format!(
+        "{} {} {}",
+        pick_pair(SURNAMES, sex),
+        first,
+        pick_pair(FATHER_NAMES, sex),
+    ))
+}
+
+/// Сгенерировать короткую биографию
+pub fn random_bio(
+    prof: Profession,
+    sex: Sex,
+) -> Bio {
+    Bio(format!(
+        "{}. {}, {}.",
+        pick_pair(ORIGINS, sex),
+        prof,
+        pick_pair(TRAITS, sex),
+    ))
+}
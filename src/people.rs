@@ -3,9 +3,15 @@ use std::hash::Hash;
 use std::collections::HashMap;
 
 use legion::*;
+use rand::thread_rng;
+use rand::Rng;
+use serde::Deserialize;
 
 use crate::core::*;
 use crate::area::*;
+use crate::mood::*;
+use crate::names::*;
+use crate::coupons::*;
 
 /// Сколько места занимает человек
 pub static COMRAD_RENTED_PLACE: usize = 1000;
@@ -22,15 +28,199 @@ pub static COMRAD_RENTED_PLACE: usize = 1000;
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Satiety(pub u16);
 
+/// Сколько сытости уходит за смену (день)
+pub static SATIETY_PER_SHIFT: u16 = 10;
+/// Ниже - голодание с дебафами настроения
+pub static SATIETY_HUNGER: u16 = 100;
+/// Ниже - пачка концентрата насыщает лучше
+pub static SATIETY_FULL: u16 = 190;
+/// Выше - ожирение с дебафами настроения
+pub static SATIETY_OBESITY: u16 = 200;
+/// Столько сытости у новенького: хорошо поел, но без ожирения
+pub static SATIETY_WELL_FED: u16 = 190;
+/// Больше стольких пачек за смену в человека не лезет
+pub static MEAL_MAX_PACKS: usize = 3;
+
+/// Сколько сытости добавляет пачка концентрата
+pub fn pack_nutrition(
+    sat: Satiety,
+) -> u16 {
+    if sat.0 < SATIETY_FULL {
+        11
+    } else {
+        10
+    }
+}
+
+/// Сколько пачек съест человек, если дать ему вволю.
+/// Ест пока не наестся, но так, чтобы не перейти в ожирение.
+pub fn packs_wanted(
+    sat: Satiety,
+) -> usize {
+    let mut sat = sat;
+    let mut packs = 0;
+    while packs < MEAL_MAX_PACKS && sat.0 + pack_nutrition(sat) <= SATIETY_OBESITY {
+        sat = eat_pack(sat);
+        packs += 1;
+    }
+    packs
+}
+
+/// Съесть пачку концентрата
+pub fn eat_pack(
+    sat: Satiety,
+) -> Satiety {
+    Satiety(sat.0 + pack_nutrition(sat))
+}
+
+/// Прожить смену: сытость падает, на нуле человек умирает.
+/// Возвращает None если человек умер от голода.
+pub fn starve_shift(
+    sat: Satiety,
+) -> Option<Satiety> {
+    match sat.0.saturating_sub(SATIETY_PER_SHIFT) {
+        0 => None,
+        s => Some(Satiety(s)),
+    }
+}
+
+/// Пайковая политика: кто и сколько ест, когда концентрата мало
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RationPolicy {
+    Full, // Полный паек. Первыми едят самые голодные.
+    Half, // Половинный паек. Выдается пачка на двоих в смену.
+    ByProfession, // Первыми едят ликвидаторы, потом рабочие, потом остальные.
+    ByTier, // Первыми едят старшие разряды.
+}
+
+impl fmt::Display for RationPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RationPolicy::Full         => write!(f, "Полный паек"),
+            RationPolicy::Half         => write!(f, "Половинный паек"),
+            RationPolicy::ByProfession => write!(f, "По профессиям"),
+            RationPolicy::ByTier       => write!(f, "По разрядам"),
+        }
+    }
+}
+
+/// FIXME: надо генерить список напрямую из энума.
+pub fn all_ration_policies() -> Vec<RationPolicy> {
+    vec![
+        RationPolicy::Full,
+        RationPolicy::Half,
+        RationPolicy::ByProfession,
+        RationPolicy::ByTier,
+    ]
+}
+
+/// Очередность профессий при раздаче по профессиям. Меньше - раньше.
+fn profession_ration_priority(
+    prof: Profession,
+) -> usize {
+    match prof {
+        Profession::Likvidator => 0,
+        Profession::Worker => 1,
+        Profession::Stalker => 2,
+        Profession::Scientist => 3,
+        Profession::Party => 4,
+        Profession::NoProf => 5,
+    }
+}
+
+/// Сколько пачек выдать каждому едоку при данной политике.
+/// Возвращает количество пачек в том же порядке, что и едоки.
+pub fn ration_plan(
+    eaters: &[(Profession, Tier, Satiety)],
+    stock: usize,
+    policy: RationPolicy,
+) -> Vec<usize> {
+    let wanted: Vec<usize> = eaters
+        .iter()
+        .map(|(_, _, sat)| packs_wanted(*sat))
+        .collect();
+    let mut budget = match policy {
+        RationPolicy::Half => std::cmp::min(stock, eaters.len().div_ceil(2)),
+        _ => stock,
+    };
+    let mut order: Vec<usize> = (0..eaters.len()).collect();
+    // внутри любой очереди первыми едят самые голодные
+    order.sort_by_key(|i| {
+        let (prof, tier, sat) = eaters[*i];
+        match policy {
+            RationPolicy::Full | RationPolicy::Half => (0, 0, sat.0),
+            RationPolicy::ByProfession => (profession_ration_priority(prof), 0, sat.0),
+            RationPolicy::ByTier => (0, Tier::T3 as usize - tier as usize, sat.0),
+        }
+    });
+    let mut result = vec![0; eaters.len()];
+    for i in order {
+        let packs = std::cmp::min(wanted[i], budget);
+        result[i] = packs;
+        budget -= packs;
+    }
+    result
+}
+
+/// На сколько смен хватит концентрата при данной политике.
+/// Прогоняем смены вперед, пока склад не опустеет. Больше года не считаем.
+pub fn predict_days_of_food(
+    eaters: &[(Profession, Tier, Satiety)],
+    stock: usize,
+    policy: RationPolicy,
+) -> usize {
+    let mut eaters: Vec<(Profession, Tier, Satiety)> = eaters.to_vec();
+    let mut stock = stock;
+    let mut days = 0;
+    while stock > 0 && !eaters.is_empty() && days < 365 {
+        eaters = eaters
+            .into_iter()
+            .filter_map(|(p, t, sat)| starve_shift(sat).map(|s| (p, t, s)))
+            .collect();
+        let plan = ration_plan(&eaters, stock, policy);
+        for (eater, packs) in eaters.iter_mut().zip(plan.iter()) {
+            for _ in 0..*packs {
+                eater.2 = eat_pack(eater.2);
+            }
+            stock -= packs;
+        }
+        days += 1;
+    }
+    days
+}
+
+/// Едоки колонии для расчета пайков
+pub fn eaters(
+    world: &mut World,
+) -> Vec<(Profession, Tier, Satiety)> {
+    let mut query = <(&Profession, &Tier, &Satiety)>::query();
+    query
+        .iter(world)
+        .map(|(p, t, s)| (*p, *t, *s))
+        .collect()
+}
+
 /// Настроение комрада.
 /// Больше 10 быть не должно. 10 - счастлив.
 /// 5, 6 - нейтрал
 /// 0 - тотально несчастлив.
+/// Каждую смену пересчитывается из факторов, см. MoodFactors.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Mood(pub u8);
 
+/// Больше 10 настроения не бывает
+pub static MOOD_MAX: u8 = 10;
+
+impl Mood {
+    /// Изменить настроение, не выходя за пределы 0..=10
+    pub fn change(&mut self, delta: i8) {
+        let changed = self.0 as i16 + delta as i16;
+        self.0 = changed.max(0).min(MOOD_MAX as i16) as u8;
+    }
+}
+
 /// Профессия
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
 pub enum Profession {
     NoProf, // Нет профессии
     Stalker, // Мусорщик
@@ -43,16 +233,98 @@ pub enum Profession {
 impl fmt::Display for Profession {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Profession::NoProf     => write!(f, "{}", "Тунеядец"),
-            Profession::Stalker    => write!(f, "{}", "Мусорщик"),
-            Profession::Likvidator => write!(f, "{}", "Ликвидатор"),
-            Profession::Scientist  => write!(f, "{}", "Ученый"),
-            Profession::Worker     => write!(f, "{}", "Рабочий"),
-            Profession::Party      => write!(f, "{}", "Партийный функционер"),
+            Profession::NoProf     => write!(f, "Тунеядец"),
+            Profession::Stalker    => write!(f, "Мусорщик"),
+            Profession::Likvidator => write!(f, "Ликвидатор"),
+            Profession::Scientist  => write!(f, "Ученый"),
+            Profession::Worker     => write!(f, "Рабочий"),
+            Profession::Party      => write!(f, "Партийный функционер"),
+        }
+    }
+}
+
+/// FIXME: надо генерить список напрямую из энума.
+pub fn all_professions() -> Vec<Profession> {
+    vec![
+        Profession::NoProf,
+        Profession::Stalker,
+        Profession::Likvidator,
+        Profession::Scientist,
+        Profession::Worker,
+        Profession::Party,
+    ]
+}
+
+/// Пол
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sex {
+    Male,
+    Female,
+}
+
+impl fmt::Display for Sex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Sex::Male   => write!(f, "муж."),
+            Sex::Female => write!(f, "жен."),
+        }
+    }
+}
+
+/// ФИО
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Name(pub String);
+
+/// Смен в году. Одна смена - один день.
+pub static SHIFTS_PER_YEAR: usize = 365;
+
+/// Возраст в сменах
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Age(pub usize);
+
+impl Age {
+    /// Полных лет
+    pub fn years(&self) -> usize {
+        self.0 / SHIFTS_PER_YEAR
+    }
+}
+
+/// Краткая биография
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bio(pub String);
+
+/// Что происходило с комрадом: возраст и событие
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct History(pub Vec<(Age, String)>);
+
+/// Записать событие в личное дело комрада
+pub fn record_history(
+    world: &mut World,
+    comrad: Entity,
+    event: String,
+) {
+    if let Some(mut entry) = world.entry(comrad) {
+        let age = entry
+            .get_component::<Age>()
+            .ok()
+            .copied()
+            .unwrap_or(Age(0));
+        if let Ok(history) = entry.get_component_mut::<History>() {
+            history.0.push((age, event));
         }
     }
 }
 
+/// Все стареют на смену
+pub fn grow_older(
+    world: &mut World,
+) {
+    let mut query = <&mut Age>::query();
+    for age in query.iter_mut(world) {
+        age.0 += 1;
+    }
+}
+
 /// Заспавнить колониста в конкретную комнату
 pub fn spawn_comrad(
     world: &mut World,
@@ -65,9 +337,21 @@ pub fn spawn_comrad(
         tier,
         BelongsToRoom(room),
         AreaOccupied(COMRAD_RENTED_PLACE),
-        Satiety(100),
+        Satiety(SATIETY_WELL_FED),
         Mood(5),
+        MoodFactors::default(),
     ));
+    let mut rng = thread_rng();
+    let sex = if rng.gen_bool(0.5) { Sex::Male } else { Sex::Female };
+    let age = Age(rng.gen_range(18..50) * SHIFTS_PER_YEAR + rng.gen_range(0..SHIFTS_PER_YEAR));
+    if let Some(mut entry) = world.entry(entity) {
+        entry.add_component(sex);
+        entry.add_component(random_name(sex));
+        entry.add_component(age);
+        entry.add_component(random_bio(prof, sex));
+        entry.add_component(History(vec![(age, "Постановка на учет в блоке".to_string())]));
+        entry.add_component(Wallet::default());
+    }
     entity
 }
 
@@ -86,60 +370,6 @@ pub fn people_by_profession(
     result
 }
 
-/// Отряд ликвидаторов ОЛПС по стандарту №1-Ж
-pub fn spawn_1_g (
-    world: &mut World,
-    room: Entity,
-) {
-    // Сержант с огнеметом
-    spawn_comrad(
-        world,
-        Profession::Likvidator,
-        Tier::T2,
-        room,
-    );
-
-    // Пятеро огнеметчиков
-    for _ in 0..5 {
-        spawn_comrad(
-            world,
-            Profession::Likvidator,
-            Tier::T1,
-            room,
-        );
-    };
-
-    // Четверо граблистов
-    for _ in 0..4 {
-        spawn_comrad(
-            world,
-            Profession::Likvidator,
-            Tier::T1,
-            room,
-        );
-    };
-
-    // Наряд с искрой
-    for _ in 0..2 {
-        spawn_comrad(
-            world,
-            Profession::Likvidator,
-            Tier::T1,
-            room,
-        );
-    };
-
-    // Двое с Гранитом
-    for _ in 0..2 {
-        spawn_comrad(
-            world,
-            Profession::Likvidator,
-            Tier::T1,
-            room,
-        );
-    };
-}
-
 /// Сумма всего настроения в блоке
 pub fn block_mood(
     world: &mut World,
@@ -163,3 +393,98 @@ pub fn block_satiety(
     };
     Satiety(result)
 }
+
+/// Сколько пачек концентрата колония съедает за смену.
+/// Сытый человек съедает одну пачку в смену.
+pub fn concentrat_demand(
+    world: &mut World,
+) -> usize {
+    let mut query = <&Satiety>::query();
+    query.iter(world).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pack_nutrition_drops_at_full() {
+        assert_eq!(eat_pack(Satiety(SATIETY_FULL - 1)), Satiety(SATIETY_FULL + 10));
+        assert_eq!(eat_pack(Satiety(SATIETY_FULL)), Satiety(SATIETY_FULL + 10));
+        assert_eq!(eat_pack(Satiety(SATIETY_FULL + 5)), Satiety(SATIETY_FULL + 15));
+    }
+
+    #[test]
+    fn no_overeating_into_obesity() {
+        assert_eq!(packs_wanted(Satiety(SATIETY_OBESITY)), 0);
+        assert_eq!(packs_wanted(Satiety(SATIETY_FULL)), 1);
+        assert_eq!(packs_wanted(Satiety(SATIETY_HUNGER)), MEAL_MAX_PACKS);
+        assert!(eat_pack(Satiety(SATIETY_WELL_FED)).0 <= SATIETY_OBESITY);
+    }
+
+    #[test]
+    fn hunger_debuff_below_threshold() {
+        assert!(hunger_mood_factor(Satiety(SATIETY_HUNGER - 1)) < 0);
+        assert_eq!(hunger_mood_factor(Satiety(SATIETY_HUNGER)), 0);
+    }
+
+    #[test]
+    fn obesity_debuff_above_threshold() {
+        assert!(hunger_mood_factor(Satiety(SATIETY_OBESITY + 1)) < 0);
+        assert!(hunger_mood_factor(Satiety(SATIETY_OBESITY)) >= 0);
+    }
+
+    #[test]
+    fn starvation_death_without_underflow() {
+        assert_eq!(starve_shift(Satiety(SATIETY_PER_SHIFT + 1)), Some(Satiety(1)));
+        assert_eq!(starve_shift(Satiety(SATIETY_PER_SHIFT)), None);
+        assert_eq!(starve_shift(Satiety(SATIETY_PER_SHIFT - 1)), None);
+        assert_eq!(starve_shift(Satiety(0)), None);
+    }
+
+    fn eater(prof: Profession, tier: Tier, sat: u16) -> (Profession, Tier, Satiety) {
+        (prof, tier, Satiety(sat))
+    }
+
+    #[test]
+    fn full_ration_feeds_hungriest_first() {
+        let eaters = [
+            eater(Profession::Worker, Tier::T1, 150),
+            eater(Profession::Worker, Tier::T1, 50),
+        ];
+        assert_eq!(ration_plan(&eaters, 1, RationPolicy::Full), vec![0, 1]);
+        assert_eq!(ration_plan(&eaters, 100, RationPolicy::Full), vec![MEAL_MAX_PACKS, MEAL_MAX_PACKS]);
+    }
+
+    #[test]
+    fn half_ration_is_pack_per_two() {
+        let eaters = vec![eater(Profession::Worker, Tier::T1, 50); 3];
+        assert_eq!(ration_plan(&eaters, 100, RationPolicy::Half).iter().sum::<usize>(), 2);
+        assert_eq!(ration_plan(&eaters, 1, RationPolicy::Half).iter().sum::<usize>(), 1);
+    }
+
+    #[test]
+    fn priority_rations_ignore_hunger_order() {
+        let eaters = [
+            eater(Profession::Worker, Tier::T1, 50),
+            eater(Profession::Likvidator, Tier::T1, 150),
+        ];
+        assert_eq!(ration_plan(&eaters, 1, RationPolicy::ByProfession), vec![0, 1]);
+        let eaters = [
+            eater(Profession::Worker, Tier::T1, 50),
+            eater(Profession::Worker, Tier::T3, 150),
+        ];
+        assert_eq!(ration_plan(&eaters, 1, RationPolicy::ByTier), vec![0, 1]);
+    }
+
+    #[test]
+    fn food_prediction_by_policy() {
+        // сытым нужна пачка в смену на человека
+        let eaters = vec![eater(Profession::Worker, Tier::T1, SATIETY_WELL_FED); 10];
+        assert_eq!(predict_days_of_food(&eaters, 100, RationPolicy::Full), 10);
+        assert_eq!(predict_days_of_food(&eaters, 100, RationPolicy::Half), 20);
+        assert_eq!(predict_days_of_food(&eaters, 0, RationPolicy::Full), 0);
+        assert_eq!(predict_days_of_food(&[], 100, RationPolicy::Full), 0);
+        assert_eq!(predict_days_of_food(&eaters, 100000, RationPolicy::Full), 365);
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::HashMap;
+
+use legion::*;
+use rand::thread_rng;
+use rand::Rng;
+
+use crate::area::*;
+use crate::core::*;
+use crate::mood::*;
+use crate::people::*;
+use crate::turn::*;
+use crate::housing::*;
+
+/// Сколько места занимает ребенок
+pub static CHILD_RENTED_PLACE: usize = 500;
+
+/// С какого возраста ребенок ходит в детсад и школу (лет)
+pub static SCHOOL_AGE: usize = 3;
+
+/// Со скольки лет комрад взрослый и получает профессию
+pub static ADULT_AGE: usize = 16;
+
+/// Сколько смен учебы нужно, чтобы выйти из школы рабочим, а не тунеядцем
+pub static SCHOOL_SHIFTS_REQUIRED: usize = 10 * 365;
+
+/// Сколько трудочасов партийного функционера уходит за смену на одного ребенка
+pub static SCHOOL_BP_PER_CHILD: usize = 1;
+
+/// В каком возрасте можно заводить детей (лет)
+pub static FERTILE_AGE: (usize, usize) = (18, 45);
+
+/// Пенсионный возраст (лет)
+pub static RETIREMENT_AGE: usize = 60;
+
+/// С какого возраста люди начинают умирать от старости (лет)
+pub static OLD_AGE: usize = 55;
+
+/// Метка ребенка. Дети не работают.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Child;
+
+/// Сколько смен ребенок отучился в детсаду и школе
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Schooling(pub usize);
+
+/// Метка пенсионера. Пенсионеры не работают.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Retired;
+
+/// Шанс рождения ребенка у пары за смену (из 1000).
+/// Несчастные и живущие в тесноте детей не заводят.
+pub fn birth_chance(
+    avg_mood: u8,
+    free_space: usize,
+) -> usize {
+    if free_space < CHILD_RENTED_PLACE {
+        0
+    } else if avg_mood >= 8 {
+        6
+    } else if avg_mood >= MOOD_BASE as u8 {
+        3
+    } else {
+        0
+    }
+}
+
+/// Шанс умереть от старости за смену (из 10000)
+pub fn natural_death_chance(
+    age: Age,
+) -> usize {
+    age.years().saturating_sub(OLD_AGE) * 3
+}
+
+/// Заспавнить новорожденного в наименее тесную жилячейку.
+/// None - если ребенка некуда поселить.
+pub fn spawn_child(
+    world: &mut World,
+) -> Option<Entity> {
+    let room = least_crowded_living_room(world, CHILD_RENTED_PLACE)?;
+    let child = spawn_comrad(world, Profession::NoProf, Tier::NoTier, room);
+    if let Some(mut entry) = world.entry(child) {
+        let sex = entry
+            .get_component::<Sex>()
+            .ok()
+            .copied()
+            .unwrap_or(Sex::Male);
+        entry.add_component(AreaOccupied(CHILD_RENTED_PLACE));
+        entry.add_component(Age(0));
+        entry.add_component(Child);
+        entry.add_component(Schooling::default());
+        entry.add_component(Bio(match sex {
+            Sex::Male => "Родился в блоке.".to_string(),
+            Sex::Female => "Родилась в блоке.".to_string(),
+        }));
+        entry.add_component(History(vec![(Age(0), "Рождение".to_string())]));
+    }
+    Some(child)
+}
+
+/// Дать повзрослевшему место взрослого: в своей жилячейке, если там хватает места,
+/// иначе в наименее заполненной. Если места нет нигде, остается на детском месте.
+/// Возвращает, получилось ли.
+fn give_adult_place(
+    world: &mut World,
+    comrad: Entity,
+) -> bool {
+    let extra = COMRAD_RENTED_PLACE - CHILD_RENTED_PLACE;
+    let current = world
+        .entry_ref(comrad)
+        .ok()
+        .and_then(|entry| entry.get_component::<BelongsToRoom>().ok().copied())
+        .map(|BelongsToRoom(room)| room);
+    let fits_here = current
+        .and_then(|room| all_rooms_with_space(world).get(&room).map(|(_, _, free, _)| free.0 >= extra))
+        .unwrap_or(false);
+    let room = if fits_here {
+        current
+    } else {
+        least_crowded_living_room(world, COMRAD_RENTED_PLACE)
+    };
+    let room = match room {
+        Some(room) => room,
+        None => return false,
+    };
+    if let Some(mut entry) = world.entry(comrad) {
+        entry.add_component(BelongsToRoom(room));
+        entry.add_component(AreaOccupied(COMRAD_RENTED_PLACE));
+    }
+    if Some(room) != current {
+        record_history(world, comrad, "Переселение в другую жилячейку".to_string());
+    }
+    true
+}
+
+/// Дети ходят в детсад и школу.
+/// Учат их партийные функционеры, трудочасы берутся из пула до распределения по заданиям.
+/// Кому не хватило учителей - смена учебы пропала.
+pub fn school_tick(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    let mut pool = resources
+        .get_mut::<BuildPowerPool>()
+        .unwrap();
+    let mut teaching = pool
+        .get(&Profession::Party)
+        .map(|by_tier| by_tier.values().map(|bp| bp.0).sum::<usize>())
+        .unwrap_or(0);
+    let mut schools = <(&AreaType, &TaskStatus)>::query();
+    let has_school = schools
+        .iter(world)
+        .any(|(atype, status)| *atype == AreaType::Party && *status == TaskStatus::Ready);
+    if !has_school {
+        return;
+    }
+
+    let mut spent = 0;
+    let mut query = <(&Age, &mut Schooling)>::query().filter(component::<Child>());
+    for (age, schooling) in query.iter_mut(world) {
+        if age.years() < SCHOOL_AGE {
+            continue;
+        }
+        if teaching < SCHOOL_BP_PER_CHILD {
+            break;
+        }
+        teaching -= SCHOOL_BP_PER_CHILD;
+        spent += SCHOOL_BP_PER_CHILD;
+        schooling.0 += 1;
+    }
+
+    // списываем учителей из пула, начиная с младших разрядов
+    if let Some(by_tier) = pool.get_mut(&Profession::Party) {
+        for tier in [Tier::T1, Tier::T2, Tier::T3].iter() {
+            if let Some(bp) = by_tier.get_mut(tier) {
+                let take = bp.0.min(spent);
+                bp.0 -= take;
+                spent -= take;
+            }
+        }
+    }
+}
+
+/// Рождения, взросление, пенсии и смерти от старости
+pub fn population_tick(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    // рождения: пары в жилячейках
+    let rooms = all_rooms_with_space(world);
+    let mut cells: HashMap<Entity, (bool, bool, usize, usize)> = HashMap::new();
+    let mut query = <(&BelongsToRoom, &Sex, &Age, &Mood)>::query()
+        .filter(!component::<Child>());
+    for (BelongsToRoom(room), sex, age, mood) in query.iter(world) {
+        let years = age.years();
+        if years < FERTILE_AGE.0 || years > FERTILE_AGE.1 {
+            continue;
+        }
+        let cell = cells.entry(*room).or_insert((false, false, 0, 0));
+        match sex {
+            Sex::Male => cell.0 = true,
+            Sex::Female => cell.1 = true,
+        }
+        cell.2 += mood.0 as usize;
+        cell.3 += 1;
+    }
+    let mut births = 0;
+    for (room, (male, female, mood_sum, adults)) in cells {
+        let free = match rooms.get(&room) {
+            Some((AreaType::Living, _, free, _)) => free.0,
+            _ => continue,
+        };
+        if !(male && female) {
+            continue;
+        }
+        let avg_mood = (mood_sum / adults) as u8;
+        if thread_rng().gen_range(0..1000) < birth_chance(avg_mood, free) &&
+            spawn_child(world).is_some()
+        {
+            births += 1;
+        }
+    }
+    if births > 0 {
+        report(resources, format!("Родилось детей: {}", births));
+    }
+
+    // взрослые, которым при совершеннолетии не хватило места, ждут расселения
+    let mut query = <(Entity, &AreaOccupied)>::query()
+        .filter(component::<Profession>() & !component::<Child>());
+    let cramped: Vec<Entity> = query
+        .iter(world)
+        .filter(|(_, occupied)| occupied.0 == CHILD_RENTED_PLACE)
+        .map(|(e, _)| *e)
+        .collect();
+    let mut still_cramped = 0;
+    for comrad in cramped {
+        if !give_adult_place(world, comrad) {
+            still_cramped += 1;
+        }
+    }
+
+    // взросление: выучившиеся становятся рабочими, недоучки - тунеядцами
+    let mut query = <(Entity, &Age, &Schooling)>::query().filter(component::<Child>());
+    let grown: Vec<(Entity, Schooling)> = query
+        .iter(world)
+        .filter(|(_, age, _)| age.years() >= ADULT_AGE)
+        .map(|(e, _, s)| (*e, *s))
+        .collect();
+    for (comrad, schooling) in grown.iter() {
+        let prof = if schooling.0 >= SCHOOL_SHIFTS_REQUIRED {
+            Profession::Worker
+        } else {
+            Profession::NoProf
+        };
+        if let Some(mut entry) = world.entry(*comrad) {
+            entry.remove_component::<Child>();
+            entry.remove_component::<Schooling>();
+            entry.add_component(prof);
+            entry.add_component(Tier::T1);
+        }
+        record_history(world, *comrad, format!("Совершеннолетие: {}", prof));
+        if !give_adult_place(world, *comrad) {
+            still_cramped += 1;
+        }
+    }
+    if !grown.is_empty() {
+        report(resources, format!("Стали взрослыми: {} чел", grown.len()));
+    }
+    if still_cramped > 0 {
+        report(resources, format!("Взрослые ютятся на детском месте, жилья нет: {} чел", still_cramped));
+    }
+
+    // пенсия
+    let mut query = <(Entity, &Age)>::query()
+        .filter(!component::<Child>() & !component::<Retired>());
+    let retiring: Vec<Entity> = query
+        .iter(world)
+        .filter(|(_, age)| age.years() >= RETIREMENT_AGE)
+        .map(|(e, _)| *e)
+        .collect();
+    for comrad in retiring.iter() {
+        if let Some(mut entry) = world.entry(*comrad) {
+            entry.add_component(Retired);
+        }
+        record_history(world, *comrad, "Выход на пенсию".to_string());
+    }
+    if !retiring.is_empty() {
+        report(resources, format!("Вышли на пенсию: {} чел", retiring.len()));
+    }
+
+    // смерть от старости, шанс из 10000
+    let mut query = <(Entity, &Age)>::query();
+    let died: Vec<Entity> = query
+        .iter(world)
+        .filter(|(_, age)| thread_rng().gen_range(0..10000) < natural_death_chance(**age))
+        .map(|(e, _)| *e)
+        .collect();
+    for comrad in died.iter() {
+        world.remove(*comrad);
+    }
+    if !died.is_empty() {
+        remember_deaths(resources, died.len());
+        report(resources, format!("Умерло от старости: {} чел", died.len()));
+    }
+}
+
+/// Половозрастная пирамида: с какого возраста десятилетие, мужчин, женщин.
+/// Старшие сверху.
+pub fn age_pyramid(
+    world: &World,
+) -> Vec<(usize, usize, usize)> {
+    let mut bins: HashMap<usize, (usize, usize)> = HashMap::new();
+    let mut query = <(&Sex, &Age)>::query();
+    for (sex, age) in query.iter(world) {
+        let bin = bins.entry(age.years() / 10).or_insert((0, 0));
+        match sex {
+            Sex::Male => bin.0 += 1,
+            Sex::Female => bin.1 += 1,
+        }
+    }
+    let oldest = bins.keys().max().copied().unwrap_or(0);
+    (0..=oldest)
+        .rev()
+        .map(|decade| {
+            let (m, f) = bins.get(&decade).copied().unwrap_or((0, 0));
+            (decade * 10, m, f)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::production::*;
+
+    fn living_cell(world: &mut World) -> Entity {
+        let cell = install_germ(world, Germ::GermT1, AreaType::Living);
+        if let Some(mut entry) = world.entry(cell) {
+            entry.add_component(TaskStatus::Ready);
+            entry.remove_component::<TaskProgress>();
+        }
+        cell
+    }
+
+    fn room_of(world: &World, comrad: Entity) -> (Entity, AreaOccupied) {
+        let entry = world.entry_ref(comrad).unwrap();
+        let BelongsToRoom(room) = *entry.get_component::<BelongsToRoom>().unwrap();
+        (room, *entry.get_component::<AreaOccupied>().unwrap())
+    }
+
+    #[test]
+    fn grown_up_does_not_overfill_cell() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let cell = living_cell(&mut world);
+        let capacity = all_rooms_with_space(&mut world)[&cell].1;
+        for _ in 0..(capacity.0 - 2 * CHILD_RENTED_PLACE) / COMRAD_RENTED_PLACE {
+            spawn_comrad(&mut world, Profession::Worker, Tier::T1, cell);
+        }
+        let teen = spawn_child(&mut world).unwrap();
+        spawn_child(&mut world).unwrap();
+        assert_eq!(all_rooms_with_space(&mut world)[&cell].2, AreaFree(0));
+        if let Some(mut entry) = world.entry(teen) {
+            entry.add_component(Age(ADULT_AGE * SHIFTS_PER_YEAR));
+        }
+
+        population_tick(&mut world, &mut resources);
+        let entry = world.entry_ref(teen).unwrap();
+        assert!(entry.get_component::<Child>().is_err());
+        assert_eq!(*entry.get_component::<Tier>().unwrap(), Tier::T1);
+        assert_eq!(room_of(&world, teen), (cell, AreaOccupied(CHILD_RENTED_PLACE)));
+        assert_eq!(all_rooms_with_space(&mut world)[&cell].3, AreaOccupied(capacity.0));
+
+        let new_cell = living_cell(&mut world);
+        population_tick(&mut world, &mut resources);
+        assert_eq!(room_of(&world, teen), (new_cell, AreaOccupied(COMRAD_RENTED_PLACE)));
+    }
+}
@@ -1,4 +1,5 @@
 use legion::*;
+use serde::Deserialize;
 use std::{
     fmt,
     hash::Hash,
@@ -6,8 +7,10 @@ use std::{
 };
 use crate::{
     area::*,
+    content::*,
     core::*,
     people::*,
+    research::*,
     resources::*,
     storage::*
 };
@@ -28,7 +31,7 @@ pub struct Priority(pub usize);
 pub struct BelongsToStationary (pub Entity);
 
 /// Стационарные объекты
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
 pub enum Stationary {
     None, // Отсутствие постройки. Заглушка для обозначения того,
     // что некоторые производственные задачи не требуют
@@ -52,15 +55,15 @@ impl fmt::Display for Stationary {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Stationary::None => write!(f, "оборудования не требуется"),
-            Stationary::BenchToolT1 => write!(f, "{}", "Верстак"),
-            Stationary::BenchToolT2 => write!(f, "{}", "Токарно-фрезерный станок"),
-            Stationary::BenchToolT3 => write!(f, "{}", "Молекулярный принтер"),
-            Stationary::FormatFurnace => write!(f, "{}", "Гравитационная печь"),
-            Stationary::LabT1 => write!(f, "{}", "Лаборатория"),
-            Stationary::LabT2 => write!(f, "{}", "Продвинутая лаборатория"),
-            Stationary::LabT3 => write!(f, "{}", "Супер лаборатория"),
-            Stationary::Barrel => write!(f, "{}", "Чан"),
-            Stationary::NeuroTerminal => write!(f, "{}", "Нейротерминал"),
+            Stationary::BenchToolT1 => write!(f, "Верстак"),
+            Stationary::BenchToolT2 => write!(f, "Токарно-фрезерный станок"),
+            Stationary::BenchToolT3 => write!(f, "Молекулярный принтер"),
+            Stationary::FormatFurnace => write!(f, "Гравитационная печь"),
+            Stationary::LabT1 => write!(f, "Лаборатория"),
+            Stationary::LabT2 => write!(f, "Продвинутая лаборатория"),
+            Stationary::LabT3 => write!(f, "Супер лаборатория"),
+            Stationary::Barrel => write!(f, "Чан"),
+            Stationary::NeuroTerminal => write!(f, "Нейротерминал"),
         }
     }
 }
@@ -84,13 +87,32 @@ pub fn all_stationaries () -> Vec<Stationary> {
 
 /// Гермкомплект. Инфраструктура конкертного помещения.
 /// Т1 - Жилячейка, Т2 - Цех/Казарма/Лаборатория/Склад, T3 - Гигацех, Суперзавод итд
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
 pub enum Germ {
     GermT1,
     GermT2,
     GermT3,
 }
 
+impl fmt::Display for Germ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Germ::GermT1 => write!(f, "Гермокомплект T1"),
+            Germ::GermT2 => write!(f, "Гермокомплект T2"),
+            Germ::GermT3 => write!(f, "Гигагерма"),
+        }
+    }
+}
+
+/// FIXME: надо генерить список напрямую из энума.
+pub fn all_germs() -> Vec<Germ> {
+    vec![
+        Germ::GermT1,
+        Germ::GermT2,
+        Germ::GermT3,
+    ]
+}
+
 /// Прогресс постройки
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct TaskProgress {
@@ -113,18 +135,10 @@ impl fmt::Display for TaskProgress {
 pub fn stationary_size (
     stationary: Stationary,
 ) -> AreaOccupied {
-    match stationary  {
-        Stationary::None => AreaOccupied(0),
-        Stationary::BenchToolT1 => AreaOccupied(2000),
-        Stationary::BenchToolT2 => AreaOccupied(2500),
-        Stationary::BenchToolT3 => AreaOccupied(5000),
-        Stationary::FormatFurnace => AreaOccupied(5000),
-        Stationary::LabT1 => AreaOccupied(2000),
-        Stationary::LabT2 => AreaOccupied(4000),
-        Stationary::LabT3 => AreaOccupied(6000),
-        Stationary::Barrel => AreaOccupied(1500),
-        Stationary::NeuroTerminal => AreaOccupied(500),
+    if stationary == Stationary::None {
+        return AreaOccupied(0);
     }
+    AreaOccupied(CONTENT.stationary(stationary).size)
 }
 
 /// Поставить герму + обустроить помещение
@@ -143,15 +157,56 @@ pub fn install_germ(
     ))
 }
 
-/// Вместимость гермы
-fn germ_capacity(germ: Germ) -> AreaCapacity {
+/// Под какие помещения годится герма
+pub fn germ_purposes(
+    germ: Germ,
+) -> Vec<AreaType> {
     match germ {
-        Germ::GermT1 => AreaCapacity(3000),
-        Germ::GermT2 => AreaCapacity(15000),
-        Germ::GermT3 => AreaCapacity(50000),
+        Germ::GermT1 => vec![
+            AreaType::Living,
+            AreaType::Science,
+        ],
+        Germ::GermT2 => vec![
+            AreaType::Science,
+            AreaType::Military,
+            AreaType::Industrial,
+            AreaType::Party,
+        ],
+        Germ::GermT3 => vec![
+            AreaType::Science,
+            AreaType::Industrial,
+            AreaType::Party,
+        ],
     }
 }
 
+/// Можем ли мы поставить такую герму.
+/// Err - чего не хватает: специалистов, оборудования, исследований.
+pub fn can_install_germ(
+    world: &mut World,
+    tree: &TechTree,
+    researched: &Researched,
+    germ: Germ,
+) -> Result<(), Box<Shortage>> {
+    let mut missing = missing_requirements(
+        world,
+        HashMap::new(),
+        germ_requirements(germ),
+        HashMap::new(),
+    );
+    missing.research = missing_research(tree, researched, Unlock::Germ(germ));
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(Box::new(missing))
+    }
+}
+
+/// Вместимость гермы
+fn germ_capacity(germ: Germ) -> AreaCapacity {
+    AreaCapacity(CONTENT.germ(germ).capacity)
+}
+
 /// Трудочасы
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
 pub struct BuildPower(pub usize);
@@ -176,12 +231,12 @@ impl SubAssign for BuildPower {
     }
 }
 
-/// Эффективность камрада
+/// Эффективность камрада. Без разряда - дети - не работают.
 pub fn tier2comrad_buildpower(
     tier: Tier
 ) -> BuildPower {
     match tier {
-        Tier::NoTier => unreachable!(),
+        Tier::NoTier => BuildPower(0),
         Tier::T1 => BuildPower(10),
         Tier::T2 => BuildPower(20),
         Tier::T3 => BuildPower(40),
@@ -213,54 +268,35 @@ pub fn buildpower_downgrage_coef(
 pub fn stationary_build_power(
     stationary: Stationary,
 ) -> BuildPower{
-    match stationary {
-        Stationary::None => BuildPower(usize::MAX),
-        Stationary::BenchToolT1 => BuildPower(10),
-        Stationary::BenchToolT2 => BuildPower(20),
-        Stationary::BenchToolT3 => BuildPower(40),
-        Stationary::FormatFurnace => BuildPower(10),
-        Stationary::LabT1 => BuildPower(10),
-        Stationary::LabT2 => BuildPower(10),
-        Stationary::LabT3 => BuildPower(10),
-        Stationary::Barrel => BuildPower(10),
-        Stationary::NeuroTerminal => BuildPower(10),
+    if stationary == Stationary::None {
+        return BuildPower(usize::MAX);
     }
+    BuildPower(CONTENT.stationary(stationary).build_power)
 }
 
 /// Что нужно по ресурсам чтобы поставить эту стационарку
 pub fn stationary_required_resources (
     stationary: Stationary,
 ) -> HashMap<Resource, RealUnits> {
-    match stationary {
-        Stationary::None => HashMap::new(),
-        Stationary::BenchToolT1 => [
-            (Resource::ScrapT1, RealUnits (1))
-        ].iter().cloned().collect(),
-        Stationary::BenchToolT2 => [
-            (Resource::ScrapT1, RealUnits (1))
-        ].iter().cloned().collect(),
-        Stationary::BenchToolT3 => [
-            (Resource::ScrapT1, RealUnits (1))
-        ].iter().cloned().collect(),
-        Stationary::FormatFurnace => [
-            (Resource::ScrapT1, RealUnits (1))
-        ].iter().cloned().collect(),
-        Stationary::LabT1 => [
-            (Resource::ScrapT1, RealUnits (1))
-        ].iter().cloned().collect(),
-        Stationary::LabT2 => [
-            (Resource::ScrapT1, RealUnits (1))
-        ].iter().cloned().collect(),
-        Stationary::LabT3 => [
-            (Resource::ScrapT1, RealUnits (1))
-        ].iter().cloned().collect(),
-        Stationary::Barrel => [
-            (Resource::ScrapT1, RealUnits (1))
-        ].iter().cloned().collect(),
-        Stationary::NeuroTerminal => [
-            (Resource::ScrapT1, RealUnits (1))
-        ].iter().cloned().collect(),
+    if stationary == Stationary::None {
+        return HashMap::new();
+    }
+    CONTENT
+        .stationary(stationary)
+        .resources
+        .iter()
+        .map(|(res, amount)| (*res, RealUnits(*amount)))
+        .collect()
+}
+
+/// Какое оборудование уже должно стоять, чтобы построить эту стационарку
+pub fn stationary_prerequisites(
+    stationary: Stationary,
+) -> HashSet<Stationary> {
+    if stationary == Stationary::None {
+        return HashSet::new();
     }
+    CONTENT.stationary(stationary).prerequisites.iter().cloned().collect()
 }
 
 /// Метаданные по рабочей задаче
@@ -329,113 +365,17 @@ pub struct TaskPriority (pub usize);
 pub fn stationary_requirements(
     target: Stationary,
 ) -> HashSet<TaskMeta> {
-    match target {
-        Stationary::BenchToolT1 => [
-            TaskMeta {
-                prof: Profession::Worker,
-                tier: Tier::T1,
-                bp: BuildPower(1000),
-                stationary: Stationary::None,
-            },
-        ].iter().cloned().collect(),
-        Stationary::BenchToolT2 => [
-            TaskMeta {
-                prof: Profession::Worker,
-                tier: Tier::T1,
-                bp: BuildPower(10),
-                stationary: Stationary::None,
-            },
-        ].iter().cloned().collect(),
-        Stationary::BenchToolT3 => [
-            TaskMeta {
-                prof: Profession::Worker,
-                tier: Tier::T1,
-                bp: BuildPower(10),
-                stationary: Stationary::None,
-            },
-        ].iter().cloned().collect(),
-        Stationary::FormatFurnace => [
-            TaskMeta {
-                prof: Profession::Worker,
-                tier: Tier::T1,
-                bp: BuildPower(10),
-                stationary: Stationary::None,
-            },
-        ].iter().cloned().collect(),
-        Stationary::LabT1 => [
-            TaskMeta {
-                prof: Profession::Worker,
-                tier: Tier::T1,
-                bp: BuildPower(10),
-                stationary: Stationary::None,
-            },
-        ].iter().cloned().collect(),
-        Stationary::LabT2 => [
-            TaskMeta {
-                prof: Profession::Worker,
-                tier: Tier::T1,
-                bp: BuildPower(10),
-                stationary: Stationary::None,
-            },
-        ].iter().cloned().collect(),
-        Stationary::LabT3 => [
-            TaskMeta {
-                prof: Profession::Worker,
-                tier: Tier::T1,
-                bp: BuildPower(10),
-                stationary: Stationary::None,
-            },
-        ].iter().cloned().collect(),
-        Stationary::Barrel => [
-            TaskMeta {
-                prof: Profession::Worker,
-                tier: Tier::T1,
-                bp: BuildPower(10),
-                stationary: Stationary::None,
-            },
-        ].iter().cloned().collect(),
-        Stationary::NeuroTerminal => [
-            TaskMeta {
-                prof: Profession::Worker,
-                tier: Tier::T1,
-                bp: BuildPower(10),
-                stationary: Stationary::None,
-            },
-        ].iter().cloned().collect(),
-        Stationary::None => HashSet::new (),
+    if target == Stationary::None {
+        return HashSet::new();
     }
+    CONTENT.stationary(target).labor.iter().map(LaborDef::task_meta).collect()
 }
 
 /// Что надо по рабочим/оборудованию чтобы построить такую герму
 pub fn germ_requirements(
     germ: Germ,
 ) -> HashSet<TaskMeta> {
-    match germ {
-        Germ::GermT1 => [
-            TaskMeta {
-                prof: Profession::Worker,
-                tier: Tier::T1,
-                bp: BuildPower(10),
-                stationary: Stationary::None,
-            },
-        ].iter().cloned().collect(),
-        Germ::GermT2 => [
-            TaskMeta {
-                prof: Profession::Worker,
-                tier: Tier::T1,
-                bp: BuildPower(10),
-                stationary: Stationary::None,
-            },
-        ].iter().cloned().collect(),
-        Germ::GermT3 => [
-            TaskMeta {
-                prof: Profession::Worker,
-                tier: Tier::T1,
-                bp: BuildPower(10),
-                stationary: Stationary::None,
-            },
-        ].iter().cloned().collect(),
-    }
+    CONTENT.germ(germ).labor.iter().map(LaborDef::task_meta).collect()
 }
 
 /// Можем ли мы начать постройку этой стационарки
@@ -443,15 +383,65 @@ pub fn germ_requirements(
 /// Ok(()) означает что всего хватает.
 pub fn can_build_stationary (
     world: &mut World,
+    tree: &TechTree,
+    researched: &Researched,
     exist_rsrcs: HashMap<Resource, RealUnits>,
     stationary: Stationary,
-) -> Result<Entity, (
-    HashSet<Stationary>,
-    HashSet<(Profession, Tier)>,
-    HashMap<Resource, RealUnits>,
-    bool // Есть ли помещение для этого всего
-)> {
-    let requrements = stationary_requirements(stationary);
+) -> Result<Entity, Box<Shortage>> {
+    let mut missing = missing_requirements(
+        world,
+        exist_rsrcs,
+        stationary_requirements(stationary),
+        stationary_required_resources(stationary),
+    );
+    let mut stat_query = <(&Stationary, &TaskStatus)>::query();
+    let exist_stnrs: HashSet<Stationary> = stat_query
+        .iter(world)
+        .filter(|(_, status)| **status == TaskStatus::Ready)
+        .map(|(stationary, _)| *stationary)
+        .collect();
+    missing.stationaries.extend(diff2hset(stationary_prerequisites(stationary).difference(&exist_stnrs)));
+    missing.research = missing_research(tree, researched, Unlock::Stationary(stationary));
+    let room = get_sufficent_room(
+        world,
+        stationary_size(stationary),
+        AreaType::Industrial,
+    );
+    missing.no_room = room.is_none();
+    match room {
+        Some(room) if missing.is_empty() => Ok(room),
+        _ => Err(Box::new(missing)),
+    }
+}
+
+/// Чего не хватает, чтобы начать работу
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Shortage {
+    pub stationaries: HashSet<Stationary>, // оборудования
+    pub people: HashSet<(Profession, Tier)>, // специалистов
+    pub resources: HashMap<Resource, RealUnits>, // ресурсов
+    pub research: Vec<String>, // исследований
+    pub no_room: bool, // некуда поставить результат
+}
+
+impl Shortage {
+    /// Всего хватает
+    pub fn is_empty(&self) -> bool {
+        self.stationaries.is_empty() &&
+            self.people.is_empty() &&
+            self.resources.is_empty() &&
+            self.research.is_empty() &&
+            !self.no_room
+    }
+}
+
+/// Чего не хватает для выполнения работы: оборудования, специалистов, ресурсов
+pub fn missing_requirements(
+    world: &mut World,
+    exist_rsrcs: HashMap<Resource, RealUnits>,
+    requrements: HashSet<TaskMeta>,
+    req_rsrcs: HashMap<Resource, RealUnits>,
+) -> Shortage {
     let mut req_stnrs = HashSet::new();
     requrements
         .iter()
@@ -469,7 +459,6 @@ pub fn can_build_stationary (
                 req_ppl.insert((req.prof, req.tier));
             }
         );
-    let req_rsrcs = stationary_required_resources(stationary);
 
     let mut stat_query =
         <(&Stationary, &TaskStatus)>::query();
@@ -485,25 +474,17 @@ pub fn can_build_stationary (
         .iter(world)
         .map(|(p, t)|(*p, *t))
         .collect();
-    let room = get_sufficent_room(
-        world,
-        stationary_size(stationary),
-        AreaType::Industrial,
-    );
-    let res_diff = what_not_enough(exist_rsrcs, req_rsrcs);
-    if exist_stnrs.is_superset(&req_stnrs) &&
-        exist_ppl.is_superset(&req_ppl) &&
-        res_diff.is_empty() &&
-        room.is_some()
-    {
-        Ok(room.unwrap())
-    } else {
-        Err((
-            diff2hset(req_stnrs.difference(&exist_stnrs)),
-            diff2hset(req_ppl.difference(&exist_ppl)),
-            res_diff,
-            room.is_some()
-        ))
+    // за задание может взяться и работник разряда повыше, как в process_tasks
+    let not_enough_ppl: HashSet<(Profession, Tier)> = req_ppl
+        .into_iter()
+        .filter(|(prof, tier)| !exist_ppl.iter().any(|(p, t)| p == prof && t >= tier))
+        .collect();
+    Shortage {
+        stationaries: diff2hset(req_stnrs.difference(&exist_stnrs)),
+        people: not_enough_ppl,
+        resources: what_not_enough(exist_rsrcs, req_rsrcs),
+        research: Vec::new(),
+        no_room: false,
     }
 }
 
@@ -532,8 +513,8 @@ pub fn what_not_enough<K: Eq+Hash+Copy, V: Into<i32>+Ord+Sub<Output=V>+Copy> (
                 let minimum:i32 = 0;
                 let right: i32 = (*rv).into();
                 let left: i32 = (*lv).into();
-                if (right - left) < minimum {
-                    // меньше нуля - значит значение в левом хешмапе больше. Нам это не интересно.
+                if (right - left) <= minimum {
+                    // не больше нуля - значит в левом хешмапе хватает. Нам это не интересно.
                 } else {
                     result.insert (*rk, *rv - *lv);
                 }
@@ -577,3 +558,106 @@ pub fn currently_building (
     };
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(prof: Profession, tier: Tier) -> HashSet<TaskMeta> {
+        [
+            TaskMeta {
+                prof,
+                tier,
+                bp: BuildPower(10),
+                stationary: Stationary::None,
+            },
+        ].iter().cloned().collect()
+    }
+
+    #[test]
+    fn senior_worker_covers_junior_task() {
+        let mut world = World::default();
+        world.push((Stationary::None, TaskStatus::Ready));
+        let room = install_germ(&mut world, Germ::GermT1, AreaType::Living);
+        spawn_comrad(&mut world, Profession::Worker, Tier::T2, room);
+        let missing = missing_requirements(&mut world, HashMap::new(), task(Profession::Worker, Tier::T1), HashMap::new());
+        assert!(missing.is_empty());
+        let missing = missing_requirements(&mut world, HashMap::new(), task(Profession::Worker, Tier::T3), HashMap::new());
+        assert_eq!(missing.people, [(Profession::Worker, Tier::T3)].iter().cloned().collect());
+        let missing = missing_requirements(&mut world, HashMap::new(), task(Profession::Scientist, Tier::T1), HashMap::new());
+        assert_eq!(missing.people, [(Profession::Scientist, Tier::T1)].iter().cloned().collect());
+    }
+
+    #[test]
+    fn no_tier_gives_no_buildpower() {
+        assert_eq!(tier2comrad_buildpower(Tier::NoTier), BuildPower(0));
+    }
+
+    fn labs_tree() -> TechTree {
+        parse_tech_tree(r#"[
+            (
+                id: "advanced_labs",
+                name: "Лабораторное дело",
+                description: "",
+                requires: [],
+                lab: LabT1,
+                tier: T1,
+                bp: 10,
+                reagents: {},
+                unlocks: [Stationary(LabT2)],
+            ),
+        ]"#).unwrap()
+    }
+
+    #[test]
+    fn shortage_lists_everything_missing() {
+        let mut world = World::default();
+        world.push((Stationary::None, TaskStatus::Ready));
+        let shortage = can_build_stationary(
+            &mut world,
+            &labs_tree(),
+            &Researched::default(),
+            HashMap::new(),
+            Stationary::LabT2,
+        ).unwrap_err();
+        assert_eq!(shortage.stationaries, [Stationary::LabT1].iter().cloned().collect());
+        assert_eq!(shortage.research, vec!["Лабораторное дело".to_string()]);
+        assert_eq!(shortage.resources, stationary_required_resources(Stationary::LabT2));
+        let people: HashSet<(Profession, Tier)> = stationary_requirements(Stationary::LabT2)
+            .iter()
+            .map(|meta| (meta.prof, meta.tier))
+            .collect();
+        assert_eq!(shortage.people, people);
+        assert!(shortage.no_room);
+    }
+
+    #[test]
+    fn stationary_builds_when_prerequisites_met() {
+        let mut world = World::default();
+        world.push((Stationary::None, TaskStatus::Ready));
+        let lab = world.push((Stationary::LabT1, TaskStatus::Constructing));
+        let room = install_germ(&mut world, Germ::GermT3, AreaType::Industrial);
+        if let Some(mut entry) = world.entry(room) {
+            entry.add_component(TaskStatus::Ready);
+        }
+        for meta in stationary_requirements(Stationary::LabT2) {
+            spawn_comrad(&mut world, meta.prof, meta.tier, room);
+        }
+        let tree = labs_tree();
+        let researched = Researched(["advanced_labs".to_string()].iter().cloned().collect());
+        let rsrcs = stationary_required_resources(Stationary::LabT2);
+
+        // недостроенная лаборатория не считается
+        let shortage = can_build_stationary(&mut world, &tree, &researched, rsrcs.clone(), Stationary::LabT2)
+            .unwrap_err();
+        assert_eq!(shortage.stationaries, [Stationary::LabT1].iter().cloned().collect());
+        assert!(shortage.research.is_empty());
+        assert!(shortage.resources.is_empty());
+        assert!(!shortage.no_room);
+
+        if let Some(mut entry) = world.entry(lab) {
+            entry.add_component(TaskStatus::Ready);
+        }
+        assert_eq!(can_build_stationary(&mut world, &tree, &researched, rsrcs, Stationary::LabT2), Ok(room));
+    }
+}
@@ -1,26 +1,34 @@
 // Специальный модуль для топ левел запросов, которые не относятся к какому то конкретному модулю, а захватывают типы из нескольких
 
+use std::fmt;
 use std::collections::HashMap;
 use legion::*;
 use crate::{
     area::*,
     core::*,
     people::*,
-    production::*
+    production::*,
+    squad::*,
+    housing::*,
+    unrest::*,
+    mood::*,
+    education::*,
+    population::*,
 };
 
 /// кто какое место занимает
 pub fn who_take_place(
     world: &mut World,
-) -> HashMap<Entity, Vec<(Profession, Tier, AreaOccupied)>> {
+) -> HashMap<Entity, Vec<(Entity, Profession, Tier, AreaOccupied)>> {
     let mut result = HashMap::new ();
-    let mut query = <(&BelongsToRoom, &Profession, &Tier, &AreaOccupied)>::query();
-    for (&BelongsToRoom(entity), prof, tier, occupied) in query.iter(world) {
+    let mut query = <(Entity, &BelongsToRoom, &Profession, &Tier, &AreaOccupied)>::query();
+    for (human, &BelongsToRoom(entity), prof, tier, occupied) in query.iter(world) {
         result
             .entry(entity)
             .and_modify(
-                |v: &mut Vec<(Profession, Tier, AreaOccupied)>| {
+                |v: &mut Vec<(Entity, Profession, Tier, AreaOccupied)>| {
                     v.push((
+                        *human,
                         *prof,
                         *tier,
                         *occupied,
@@ -30,6 +38,7 @@ pub fn who_take_place(
             .or_insert(
                 vec![
                     (
+                        *human,
                         *prof,
                         *tier,
                         *occupied,
@@ -76,3 +85,132 @@ pub fn what_take_place(
     };
     result
 }
+
+/// Чем сейчас занят комрад
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ComradStatus {
+    Working, // Работает
+    Child, // Ребенок
+    Retired, // На пенсии
+    OnMission, // На задании с отрядом
+    Sick, // Болеет
+    Training, // Учится на курсах
+    Striking, // Бастует
+    Refusing, // Отказывается работать от тоски
+}
+
+impl fmt::Display for ComradStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComradStatus::Working   => write!(f, "Работает"),
+            ComradStatus::Child     => write!(f, "Ребенок"),
+            ComradStatus::Retired   => write!(f, "На пенсии"),
+            ComradStatus::OnMission => write!(f, "На задании"),
+            ComradStatus::Sick      => write!(f, "Болеет"),
+            ComradStatus::Training  => write!(f, "Учится"),
+            ComradStatus::Striking  => write!(f, "Бастует"),
+            ComradStatus::Refusing  => write!(f, "Отказывается работать"),
+        }
+    }
+}
+
+/// FIXME: надо генерить список напрямую из энума.
+pub fn all_comrad_statuses() -> Vec<ComradStatus> {
+    vec![
+        ComradStatus::Working,
+        ComradStatus::Child,
+        ComradStatus::Retired,
+        ComradStatus::OnMission,
+        ComradStatus::Sick,
+        ComradStatus::Training,
+        ComradStatus::Striking,
+        ComradStatus::Refusing,
+    ]
+}
+
+/// Строка списка людей
+#[derive(Clone, Debug, PartialEq)]
+pub struct RosterEntry {
+    pub comrad: Entity,
+    pub name: Name,
+    pub sex: Sex,
+    pub age: Age,
+    pub prof: Profession,
+    pub tier: Tier,
+    pub room: Entity,
+    pub satiety: Satiety,
+    pub mood: Mood,
+    pub status: ComradStatus,
+}
+
+/// Что сейчас делает комрад
+pub fn comrad_status(
+    world: &World,
+    comrad: Entity,
+) -> ComradStatus {
+    let entry = match world.entry_ref(comrad) {
+        Ok(entry) => entry,
+        Err(_) => return ComradStatus::Working,
+    };
+    let on_mission = entry
+        .get_component::<InSquad>()
+        .ok()
+        .and_then(|in_squad| world.entry_ref(in_squad.squad).ok())
+        .and_then(|squad| squad.get_component::<SquadMission>().ok().copied())
+        .map(|m| m.mission != Mission::Idle)
+        .unwrap_or(false);
+    if entry.get_component::<Child>().is_ok() {
+        ComradStatus::Child
+    } else if entry.get_component::<Retired>().is_ok() {
+        ComradStatus::Retired
+    } else if on_mission {
+        ComradStatus::OnMission
+    } else if entry.get_component::<InTraining>().is_ok() {
+        ComradStatus::Training
+    } else if entry.get_component::<Sick>().is_ok() {
+        ComradStatus::Sick
+    } else if entry.get_component::<OnStrike>().is_ok() {
+        ComradStatus::Striking
+    } else if entry.get_component::<Mood>().map(|m| m.0 < MOOD_REFUSE_WORK).unwrap_or(false) {
+        ComradStatus::Refusing
+    } else {
+        ComradStatus::Working
+    }
+}
+
+/// Список всех людей блока
+pub fn roster(
+    world: &World,
+) -> Vec<RosterEntry> {
+    let mut query = <(
+        Entity,
+        &Profession,
+        &Tier,
+        &BelongsToRoom,
+        &Satiety,
+        &Mood,
+    )>::query();
+    let mut result = Vec::new();
+    for (comrad, prof, tier, BelongsToRoom(room), satiety, mood) in query.iter(world) {
+        let entry = match world.entry_ref(*comrad) {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        result.push(RosterEntry {
+            comrad: *comrad,
+            name: entry
+                .get_component::<Name>()
+                .cloned()
+                .unwrap_or_else(|_| Name("Безымянный".to_string())),
+            sex: entry.get_component::<Sex>().copied().unwrap_or(Sex::Male),
+            age: entry.get_component::<Age>().copied().unwrap_or(Age(0)),
+            prof: *prof,
+            tier: *tier,
+            room: *room,
+            satiety: *satiety,
+            mood: *mood,
+            status: comrad_status(world, *comrad),
+        });
+    }
+    result
+}
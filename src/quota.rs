@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+use legion::*;
+use rand::thread_rng;
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::core::*;
+use crate::people::*;
+use crate::resources::*;
+use crate::storage::*;
+use crate::trust::*;
+use crate::housing::*;
+
+/// Раз в сколько смен Главблок требует поставку
+pub static QUOTA_PERIOD: usize = 30;
+
+/// Сколько доверия дают за выполненный план
+pub static QUOTA_REWARD: u8 = 5;
+
+/// Сколько доверия максимум снимают за сорванный план
+pub static QUOTA_PENALTY: u8 = 20;
+
+/// Сколько концентрата дают за каждую единицу сверх плана
+pub static QUOTA_SURPLUS_CONCENTRAT: usize = 2;
+
+/// Какая доля перевыполнения (в процентах) заслуживает присылки специалиста
+pub static QUOTA_SURPLUS_SPECIALIST: usize = 50;
+
+/// План поставок в Главблок на текущий период
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Quota {
+    pub demand: HashMap<Resource, RealUnits>, // сколько требуют, с учетом недоимки
+    pub shipped: HashMap<Resource, RealUnits>, // сколько уже отгружено
+    pub shifts_left: usize, // сколько смен до конца периода
+}
+
+impl Default for Quota {
+    fn default() -> Self {
+        Self {
+            demand: random_quota_demand(),
+            shipped: HashMap::new(),
+            shifts_left: QUOTA_PERIOD,
+        }
+    }
+}
+
+impl Quota {
+    /// Сколько еще осталось отгрузить
+    pub fn remaining(&self) -> HashMap<Resource, RealUnits> {
+        self.demand
+            .iter()
+            .map(|(res, amount)| {
+                let shipped = self.shipped.get(res).map(|s| s.0).unwrap_or(0);
+                (*res, RealUnits(amount.0.saturating_sub(shipped)))
+            })
+            .filter(|(_, amount)| amount.0 > 0)
+            .collect()
+    }
+
+    /// Сколько отгружено сверх плана
+    pub fn surplus(&self) -> HashMap<Resource, RealUnits> {
+        self.shipped
+            .iter()
+            .map(|(res, amount)| {
+                let demand = self.demand.get(res).map(|d| d.0).unwrap_or(0);
+                (*res, RealUnits(amount.0.saturating_sub(demand)))
+            })
+            .filter(|(_, amount)| amount.0 > 0)
+            .collect()
+    }
+}
+
+/// Что Главблок может потребовать и сколько.
+/// Только то, что блок может произвести сам: сырье без пополнения не требуют.
+pub(crate) static QUOTA_CHOICES: &[(Resource, usize)] = &[
+    (Resource::ComponentT1, 15),
+    (Resource::ComponentT2, 5),
+    (Resource::Polymer, 20),
+    (Resource::Concentrat, 300),
+    (Resource::ReagentT1, 10),
+    (Resource::ReagentT2, 5),
+];
+
+/// Сгенерировать план на период: пара ресурсов из списка
+pub fn random_quota_demand() -> HashMap<Resource, RealUnits> {
+    let mut rng = thread_rng();
+    QUOTA_CHOICES
+        .choose_multiple(&mut rng, 2)
+        .map(|(res, amount)| (*res, RealUnits(amount + rng.gen_range(0..=*amount / 2))))
+        .collect()
+}
+
+/// Отгрузить ресурсы в Главблок. Списываются со склада пачкой.
+/// Отгружать можно и сверх плана.
+pub fn ship_quota(
+    world: &mut World,
+    resources: &mut Resources,
+    bunch: HashMap<Resource, RealUnits>,
+) -> Result<(), SamosborError> {
+    let bunch: HashMap<Resource, RealUnits> = bunch
+        .into_iter()
+        .filter(|(_, amount)| amount.0 > 0)
+        .collect();
+    writeoff_bunch(world, bunch.clone())?;
+    let mut quota = resources
+        .get::<Quota>()
+        .map(|q| q.clone())
+        .unwrap_or_default();
+    for (res, amount) in bunch.iter() {
+        *quota.shipped.entry(*res).or_insert(RealUnits(0)) += *amount;
+    }
+    resources.insert(quota);
+    report(resources, "Отгружена поставка в Главблок".to_string());
+    Ok(())
+}
+
+/// Наградить за перевыполнение плана.
+/// За большое перевыполнение присылают специалиста, если есть где поселить,
+/// иначе - концентрат.
+fn reward_surplus(
+    world: &mut World,
+    resources: &mut Resources,
+    surplus: usize,
+    demand: usize,
+) {
+    if surplus * 100 >= demand * QUOTA_SURPLUS_SPECIALIST {
+        let prof = *[Profession::Scientist, Profession::Party, Profession::Worker]
+            .choose(&mut thread_rng())
+            .unwrap();
+        if let Ok(comrad) = settle_comrad(world, prof, Tier::T2) {
+            record_history(world, comrad, "Направлен Главблоком в награду за перевыполнение плана".to_string());
+            report(resources, format!("Главблок прислал специалиста: {} {}", prof, Tier::T2));
+            return;
+        }
+    }
+    let concentrat = surplus * QUOTA_SURPLUS_CONCENTRAT;
+    put_resource(world, Resource::Concentrat, RealUnits(concentrat));
+    report(resources, format!("За перевыполнение плана Главблок прислал концентрат: {}", concentrat));
+}
+
+/// План поставок за смену. В конце периода подводятся итоги:
+/// недоимка переходит в следующий план и стоит доверия,
+/// перевыполнение награждается.
+pub fn quota_tick(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    let mut quota = resources
+        .get::<Quota>()
+        .map(|q| q.clone())
+        .unwrap_or_default();
+    quota.shifts_left = quota.shifts_left.saturating_sub(1);
+    if quota.shifts_left > 0 {
+        resources.insert(quota);
+        return;
+    }
+
+    let demand: usize = quota.demand.values().map(|a| a.0).sum();
+    let remaining = quota.remaining();
+    let missing: usize = remaining.values().map(|a| a.0).sum();
+    let surplus: usize = quota.surplus().values().map(|a| a.0).sum();
+
+    let mut next = Quota::default();
+    if missing > 0 {
+        let penalty = (missing * QUOTA_PENALTY as usize)
+            .checked_div(demand)
+            .unwrap_or(0)
+            .max(1);
+        change_trust(resources, -(penalty as i16), "сорван план поставок");
+        for (res, amount) in remaining {
+            *next.demand.entry(res).or_insert(RealUnits(0)) += amount;
+        }
+    } else {
+        change_trust(resources, QUOTA_REWARD as i16, "выполнен план поставок");
+        if surplus > 0 {
+            reward_surplus(world, resources, surplus, demand);
+        }
+    }
+    resources.insert(next);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::area::*;
+    use crate::production::*;
+
+    fn quota(demand: usize, shipped: usize, shifts_left: usize) -> Quota {
+        Quota {
+            demand: [(Resource::Polymer, RealUnits(demand))].iter().cloned().collect(),
+            shipped: [(Resource::Polymer, RealUnits(shipped))].iter().cloned().collect(),
+            shifts_left,
+        }
+    }
+
+    fn storage(world: &mut World) {
+        let room = install_germ(world, Germ::GermT2, AreaType::Party);
+        if let Some(mut entry) = world.entry(room) {
+            entry.add_component(TaskStatus::Ready);
+        }
+    }
+
+    #[test]
+    fn remaining_and_surplus() {
+        assert_eq!(quota(10, 4, 1).remaining().get(&Resource::Polymer), Some(&RealUnits(6)));
+        assert!(quota(10, 4, 1).surplus().is_empty());
+        assert!(quota(10, 13, 1).remaining().is_empty());
+        assert_eq!(quota(10, 13, 1).surplus().get(&Resource::Polymer), Some(&RealUnits(3)));
+    }
+
+    #[test]
+    fn shipment_is_written_off() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        storage(&mut world);
+        put_resource(&mut world, Resource::Polymer, RealUnits(8));
+        resources.insert(quota(10, 0, 5));
+        let bunch: HashMap<Resource, RealUnits> = [(Resource::Polymer, RealUnits(20))]
+            .iter()
+            .cloned()
+            .collect();
+        assert!(ship_quota(&mut world, &mut resources, bunch).is_err());
+        assert_eq!(resources.get::<Quota>().unwrap().shipped.get(&Resource::Polymer), Some(&RealUnits(0)));
+        let bunch: HashMap<Resource, RealUnits> = [(Resource::Polymer, RealUnits(5))]
+            .iter()
+            .cloned()
+            .collect();
+        assert_eq!(ship_quota(&mut world, &mut resources, bunch), Ok(()));
+        assert_eq!(resources.get::<Quota>().unwrap().shipped.get(&Resource::Polymer), Some(&RealUnits(5)));
+        assert_eq!(what_we_have(&mut world).get(&Resource::Polymer), Some(&RealUnits(3)));
+    }
+
+    #[test]
+    fn shortfall_carries_over_with_penalty() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(quota(10, 5, 2));
+        quota_tick(&mut world, &mut resources);
+        assert_eq!(resources.get::<Quota>().unwrap().shifts_left, 1);
+        assert!(resources.get::<PartyTrust>().is_none());
+        quota_tick(&mut world, &mut resources);
+        let next = resources.get::<Quota>().unwrap().clone();
+        assert_eq!(next.shifts_left, QUOTA_PERIOD);
+        assert!(next.shipped.is_empty());
+        assert!(next.demand.get(&Resource::Polymer).unwrap().0 >= 5);
+        assert_eq!(*resources.get::<PartyTrust>().unwrap(), PartyTrust(TRUST_START - QUOTA_PENALTY / 2));
+    }
+
+    #[test]
+    fn met_quota_is_rewarded() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        storage(&mut world);
+        resources.insert(quota(10, 12, 1));
+        quota_tick(&mut world, &mut resources);
+        assert_eq!(*resources.get::<PartyTrust>().unwrap(), PartyTrust(TRUST_START + QUOTA_REWARD));
+        // перевыполнение небольшое - присылают концентрат
+        assert_eq!(
+            what_we_have(&mut world).get(&Resource::Concentrat),
+            Some(&RealUnits(2 * QUOTA_SURPLUS_CONCENTRAT)),
+        );
+    }
+}
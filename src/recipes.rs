@@ -0,0 +1,551 @@
+use std::fmt;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use legion::*;
+use serde::Deserialize;
+
+use crate::core::*;
+use crate::people::*;
+use crate::production::*;
+use crate::research::*;
+use crate::resources::*;
+use crate::storage::*;
+
+/// Техпроцессы. Из одних ресурсов на станке делаются другие.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+pub enum Recipe {
+    ComponentT1, // Механический компонент из чермета
+    ComponentT2, // Электронный компонент из цветмета
+    ComponentT3, // Суперкомпонент из редких металлов
+    Polymer, // Вулканизация слизи в полимер
+    Concentrat, // Брожение слизи с биосырьем в чане
+    PressConcentrat, // Прессовка биосырья в печи
+    GatherSlime, // Сбор слизи по техническим этажам
+    GatherBioRaw, // Сбор биосырья: грибница, мох, крысы
+    ReagentT1, // Выгонка экоцида из слизи
+    ReagentT2, // Синтез компониума в лаборатории
+    ReagentT3, // Синтез сталия в продвинутой лаборатории
+}
+
+impl fmt::Display for Recipe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recipe::ComponentT1 => write!(f, "Механические компоненты"),
+            Recipe::ComponentT2 => write!(f, "Электронные компоненты"),
+            Recipe::ComponentT3 => write!(f, "Суперкомпоненты"),
+            Recipe::Polymer     => write!(f, "Вулканизация полимера"),
+            Recipe::Concentrat  => write!(f, "Брожение концентрата"),
+            Recipe::PressConcentrat => write!(f, "Прессовка концентрата"),
+            Recipe::GatherSlime => write!(f, "Сбор слизи"),
+            Recipe::GatherBioRaw => write!(f, "Сбор биосырья"),
+            Recipe::ReagentT1   => write!(f, "Выгонка экоцида"),
+            Recipe::ReagentT2   => write!(f, "Синтез компониума"),
+            Recipe::ReagentT3   => write!(f, "Синтез сталия"),
+        }
+    }
+}
+
+/// FIXME: надо генерить список напрямую из энума.
+pub fn all_recipes() -> Vec<Recipe> {
+    vec![
+        Recipe::ComponentT1,
+        Recipe::ComponentT2,
+        Recipe::ComponentT3,
+        Recipe::Polymer,
+        Recipe::Concentrat,
+        Recipe::PressConcentrat,
+        Recipe::GatherSlime,
+        Recipe::GatherBioRaw,
+        Recipe::ReagentT1,
+        Recipe::ReagentT2,
+        Recipe::ReagentT3,
+    ]
+}
+
+/// Что уходит в одну партию
+pub fn recipe_inputs(
+    recipe: Recipe,
+) -> HashMap<Resource, RealUnits> {
+    match recipe {
+        Recipe::ComponentT1 => [
+            (Resource::ScrapT1, RealUnits(2)),
+        ].iter().cloned().collect(),
+        Recipe::ComponentT2 => [
+            (Resource::ScrapT2, RealUnits(2)),
+            (Resource::ComponentT1, RealUnits(1)),
+        ].iter().cloned().collect(),
+        Recipe::ComponentT3 => [
+            (Resource::ScrapT3, RealUnits(2)),
+            (Resource::ComponentT2, RealUnits(1)),
+        ].iter().cloned().collect(),
+        Recipe::Polymer => [
+            (Resource::Slime, RealUnits(5)),
+        ].iter().cloned().collect(),
+        Recipe::Concentrat => [
+            (Resource::Slime, RealUnits(10)),
+            (Resource::BioRaw, RealUnits(10)),
+        ].iter().cloned().collect(),
+        Recipe::PressConcentrat => [
+            (Resource::BioRaw, RealUnits(20)),
+        ].iter().cloned().collect(),
+        Recipe::GatherSlime => HashMap::new(),
+        Recipe::GatherBioRaw => HashMap::new(),
+        Recipe::ReagentT1 => [
+            (Resource::Slime, RealUnits(5)),
+        ].iter().cloned().collect(),
+        Recipe::ReagentT2 => [
+            (Resource::Slime, RealUnits(5)),
+            (Resource::ReagentT1, RealUnits(1)),
+            (Resource::ComponentT1, RealUnits(1)),
+        ].iter().cloned().collect(),
+        Recipe::ReagentT3 => [
+            (Resource::Slime, RealUnits(10)),
+            (Resource::ReagentT2, RealUnits(1)),
+            (Resource::ComponentT2, RealUnits(1)),
+        ].iter().cloned().collect(),
+    }
+}
+
+/// Что получается из одной партии
+pub fn recipe_outputs(
+    recipe: Recipe,
+) -> HashMap<Resource, RealUnits> {
+    match recipe {
+        Recipe::ComponentT1 => [
+            (Resource::ComponentT1, RealUnits(1)),
+        ].iter().cloned().collect(),
+        Recipe::ComponentT2 => [
+            (Resource::ComponentT2, RealUnits(1)),
+        ].iter().cloned().collect(),
+        Recipe::ComponentT3 => [
+            (Resource::ComponentT3, RealUnits(1)),
+        ].iter().cloned().collect(),
+        Recipe::Polymer => [
+            (Resource::Polymer, RealUnits(1)),
+        ].iter().cloned().collect(),
+        Recipe::Concentrat => [
+            (Resource::Concentrat, RealUnits(40)),
+        ].iter().cloned().collect(),
+        Recipe::PressConcentrat => [
+            (Resource::Concentrat, RealUnits(30)),
+        ].iter().cloned().collect(),
+        Recipe::GatherSlime => [
+            (Resource::Slime, RealUnits(10)),
+        ].iter().cloned().collect(),
+        Recipe::GatherBioRaw => [
+            (Resource::BioRaw, RealUnits(10)),
+        ].iter().cloned().collect(),
+        Recipe::ReagentT1 => [
+            (Resource::ReagentT1, RealUnits(1)),
+        ].iter().cloned().collect(),
+        Recipe::ReagentT2 => [
+            (Resource::ReagentT2, RealUnits(1)),
+        ].iter().cloned().collect(),
+        Recipe::ReagentT3 => [
+            (Resource::ReagentT3, RealUnits(1)),
+        ].iter().cloned().collect(),
+    }
+}
+
+/// На каком оборудовании идет техпроцесс
+pub fn recipe_stationary(
+    recipe: Recipe,
+) -> Stationary {
+    match recipe {
+        Recipe::ComponentT1 => Stationary::BenchToolT1,
+        Recipe::ComponentT2 => Stationary::BenchToolT2,
+        Recipe::ComponentT3 => Stationary::BenchToolT3,
+        Recipe::Polymer => Stationary::FormatFurnace,
+        Recipe::Concentrat => Stationary::Barrel,
+        Recipe::PressConcentrat => Stationary::FormatFurnace,
+        Recipe::GatherSlime => Stationary::None,
+        Recipe::GatherBioRaw => Stationary::None,
+        Recipe::ReagentT1 => Stationary::Barrel,
+        Recipe::ReagentT2 => Stationary::LabT1,
+        Recipe::ReagentT3 => Stationary::LabT2,
+    }
+}
+
+/// Что надо по рабочим чтобы сделать одну партию.
+/// Баланс пищевой цепочки: человек съедает пачку концентрата в смену.
+/// Партия в чане (40 пачек) стоит 10 трудочасов сбора слизи,
+/// 10 трудочасов сбора биосырья и 10 трудочасов у чана -
+/// то есть один рабочий 1 разряда кормит примерно 13 человек.
+/// Печь прожорливее по биосырью, зато не требует слизи.
+/// Реактивы в лабораториях синтезируют ученые.
+pub fn recipe_requirements(
+    recipe: Recipe,
+) -> HashSet<TaskMeta> {
+    let (prof, tier, bp) = match recipe {
+        Recipe::ComponentT1 => (Profession::Worker, Tier::T1, BuildPower(10)),
+        Recipe::ComponentT2 => (Profession::Worker, Tier::T2, BuildPower(20)),
+        Recipe::ComponentT3 => (Profession::Worker, Tier::T3, BuildPower(40)),
+        Recipe::Polymer => (Profession::Worker, Tier::T1, BuildPower(10)),
+        Recipe::Concentrat => (Profession::Worker, Tier::T1, BuildPower(10)),
+        Recipe::PressConcentrat => (Profession::Worker, Tier::T1, BuildPower(10)),
+        Recipe::GatherSlime => (Profession::Worker, Tier::T1, BuildPower(10)),
+        Recipe::GatherBioRaw => (Profession::Worker, Tier::T1, BuildPower(10)),
+        Recipe::ReagentT1 => (Profession::Worker, Tier::T1, BuildPower(10)),
+        Recipe::ReagentT2 => (Profession::Scientist, Tier::T1, BuildPower(10)),
+        Recipe::ReagentT3 => (Profession::Scientist, Tier::T2, BuildPower(20)),
+    };
+    [
+        TaskMeta {
+            prof,
+            tier,
+            bp,
+            stationary: recipe_stationary(recipe),
+        },
+    ].iter().cloned().collect()
+}
+
+/// Сколько смен минимум идет техпроцесс, сколько бы рабочих на него ни бросили
+pub fn recipe_duration(
+    recipe: Recipe,
+) -> usize {
+    match recipe {
+        Recipe::ComponentT1 => 1,
+        Recipe::ComponentT2 => 1,
+        Recipe::ComponentT3 => 2,
+        Recipe::Polymer => 1,
+        Recipe::Concentrat => 2,
+        Recipe::PressConcentrat => 1,
+        Recipe::GatherSlime => 1,
+        Recipe::GatherBioRaw => 1,
+        Recipe::ReagentT1 => 1,
+        Recipe::ReagentT2 => 2,
+        Recipe::ReagentT3 => 3,
+    }
+}
+
+/// Умножить ресурсы на количество партий
+pub fn scale_resources(
+    rsrcs: HashMap<Resource, RealUnits>,
+    amount: usize,
+) -> HashMap<Resource, RealUnits> {
+    rsrcs
+        .into_iter()
+        .map(|(res, cnt)| (res, RealUnits(cnt.0 * amount)))
+        .collect()
+}
+
+/// Умножить трудозатраты на количество партий
+pub fn scale_requirements(
+    reqs: HashSet<TaskMeta>,
+    amount: usize,
+) -> HashSet<TaskMeta> {
+    reqs
+        .into_iter()
+        .map(|meta| TaskMeta {
+            bp: BuildPower(meta.bp.0 * amount),
+            ..meta
+        })
+        .collect()
+}
+
+/// Заказ на производство: техпроцесс и количество партий
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProductionBatch {
+    pub recipe: Recipe,
+    pub amount: usize,
+}
+
+/// Можем ли мы заказать столько партий.
+/// Err - чего не хватает: оборудования, специалистов, ресурсов, исследований.
+pub fn can_order_batch(
+    world: &mut World,
+    tree: &TechTree,
+    researched: &Researched,
+    exist_rsrcs: HashMap<Resource, RealUnits>,
+    recipe: Recipe,
+    amount: usize,
+) -> Result<(), Box<Shortage>> {
+    let mut missing = missing_requirements(
+        world,
+        exist_rsrcs,
+        recipe_requirements(recipe),
+        scale_resources(recipe_inputs(recipe), amount),
+    );
+    missing.research = missing_research(tree, researched, Unlock::Recipe(recipe));
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(Box::new(missing))
+    }
+}
+
+/// Заказать партии. Сырье списывается сразу.
+/// Неисследованный техпроцесс заказать нельзя.
+pub fn order_batch(
+    world: &mut World,
+    resources: &Resources,
+    recipe: Recipe,
+    amount: usize,
+) -> Result<Entity, SamosborError> {
+    let tree = resources
+        .get::<TechTree>()
+        .map(|t| t.clone())
+        .unwrap_or_default();
+    let researched = resources
+        .get::<Researched>()
+        .map(|r| r.clone())
+        .unwrap_or_default();
+    if !is_unlocked(&tree, &researched, Unlock::Recipe(recipe)) {
+        return Err(SamosborError::ResearchUnavailable);
+    }
+    writeoff_bunch(
+        world,
+        scale_resources(recipe_inputs(recipe), amount),
+    )?;
+    Ok(world.push((
+        ProductionBatch { recipe, amount },
+        ShiftsLeft(recipe_duration(recipe)),
+        TaskPriority(0),
+        TaskStatus::Constructing,
+        task_meta2progress(scale_requirements(recipe_requirements(recipe), amount)),
+    )))
+}
+
+/// Отсчитать смену техпроцессам. Готовые партии выгружаются на склад.
+/// Возвращает что не влезло на склады.
+pub fn finish_batches(
+    world: &mut World,
+) -> HashMap<Resource, RealUnits> {
+    let mut finished = Vec::new();
+    let mut query = <(
+        Entity,
+        &ProductionBatch,
+        &mut ShiftsLeft,
+        &TaskStatus,
+    )>::query();
+    for (entity, batch, shifts_left, status) in query.iter_mut(world) {
+        shifts_left.0 = shifts_left.0.saturating_sub(1);
+        if *status == TaskStatus::Ready && shifts_left.0 == 0 {
+            finished.push((*entity, *batch));
+        }
+    }
+    let mut lost = HashMap::new();
+    for (entity, batch) in finished {
+        for (res, cnt) in scale_resources(recipe_outputs(batch.recipe), batch.amount) {
+            let rest = put_resource(world, res, cnt);
+            if rest.0 > 0 {
+                *lost.entry(res).or_insert(RealUnits(0)) += rest;
+            }
+        }
+        world.remove(entity);
+    }
+    lost
+}
+
+/// Какие партии сейчас в работе: заказ, прогресс, сколько смен осталось
+pub fn batches_in_progress(
+    world: &World,
+) -> Vec<(ProductionBatch, TaskProgress, usize)> {
+    let mut result = Vec::new();
+    let mut query = <(&ProductionBatch, Option<&TaskProgress>, &ShiftsLeft)>::query();
+    for (batch, progress, shifts_left) in query.iter(world) {
+        let progress = progress.cloned().unwrap_or_else(|| {
+            let mut done = task_meta2progress(
+                scale_requirements(recipe_requirements(batch.recipe), batch.amount),
+            );
+            done.bp_invested = done.bp_required;
+            done
+        });
+        result.push((*batch, progress, shifts_left.0));
+    }
+    result.sort_by_key(|(batch, _, _)| batch.recipe);
+    result
+}
+
+/// Условие постоянного заказа
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderKind {
+    KeepInStock(RealUnits), // Держать на складе не меньше стольких единиц продукта
+    Continuous, // Производить без остановки
+}
+
+impl fmt::Display for OrderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderKind::KeepInStock(amount) => write!(f, "держать не меньше {}", amount.0),
+            OrderKind::Continuous => write!(f, "непрерывно"),
+        }
+    }
+}
+
+/// Постоянный заказ на оборудовании.
+/// Лежит в ECS рядом с BelongsToStationary того оборудования, за которым закреплен.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StandingOrder {
+    pub recipe: Recipe,
+    pub kind: OrderKind,
+    pub paused: bool,
+}
+
+/// Метка того, по какому постоянному заказу запущена партия
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FromOrder(pub Entity);
+
+/// Закрепить постоянный заказ за оборудованием
+pub fn add_standing_order(
+    world: &mut World,
+    stationary: Entity,
+    recipe: Recipe,
+    kind: OrderKind,
+) -> Entity {
+    world.push((
+        StandingOrder {
+            recipe,
+            kind,
+            paused: false,
+        },
+        BelongsToStationary(stationary),
+    ))
+}
+
+/// Поставить заказ на паузу или снять с паузы
+pub fn toggle_standing_order(
+    world: &mut World,
+    order: Entity,
+) {
+    if let Some(mut entry) = world.entry(order) {
+        if let Ok(order) = entry.get_component_mut::<StandingOrder>() {
+            order.paused = !order.paused;
+        }
+    }
+}
+
+/// Снять постоянный заказ. Уже запущенные партии доделываются.
+pub fn remove_standing_order(
+    world: &mut World,
+    order: Entity,
+) {
+    world.remove(order);
+}
+
+/// Постоянные заказы на оборудовании: заказ, оборудование, сам заказ
+pub fn standing_orders(
+    world: &World,
+) -> Vec<(Entity, Entity, StandingOrder)> {
+    let mut result = Vec::new();
+    let mut query = <(Entity, &StandingOrder, &BelongsToStationary)>::query();
+    for (entity, order, BelongsToStationary(stationary)) in query.iter(world) {
+        result.push((*entity, *stationary, *order));
+    }
+    result
+}
+
+/// Готовое оборудование, за которым можно закрепить заказы
+pub fn ready_stationaries(
+    world: &World,
+) -> Vec<(Entity, Stationary)> {
+    let mut result = Vec::new();
+    let mut query = <(Entity, &Stationary, &TaskStatus)>::query();
+    for (entity, stationary, status) in query.iter(world) {
+        if *status == TaskStatus::Ready {
+            result.push((*entity, *stationary));
+        }
+    }
+    result.sort_by_key(|(_, stationary)| *stationary);
+    result
+}
+
+/// Пора ли запускать партию по заказу
+fn order_wants_batch(
+    world: &mut World,
+    order: &StandingOrder,
+) -> bool {
+    match order.kind {
+        OrderKind::Continuous => true,
+        OrderKind::KeepInStock(threshold) => recipe_outputs(order.recipe)
+            .keys()
+            .any(|res| how_much_we_have(world, *res) < threshold),
+    }
+}
+
+/// Запустить партии по постоянным заказам.
+/// По каждому заказу запускается не больше партии за смену,
+/// а в работе не больше партий, чем смен длится техпроцесс -
+/// чтобы оборудование не простаивало, но и не копило очередь.
+pub fn run_standing_orders(
+    world: &mut World,
+    resources: &Resources,
+) {
+    let tree = resources
+        .get::<TechTree>()
+        .map(|t| t.clone())
+        .unwrap_or_default();
+    let researched = resources
+        .get::<Researched>()
+        .map(|r| r.clone())
+        .unwrap_or_default();
+    let mut in_flight: HashMap<Entity, usize> = HashMap::new();
+    let mut batch_query = <&FromOrder>::query();
+    for FromOrder(order) in batch_query.iter(world) {
+        *in_flight.entry(*order).or_insert(0) += 1;
+    }
+
+    for (order_entity, stationary, order) in standing_orders(world) {
+        let busy = in_flight.get(&order_entity).copied().unwrap_or(0) >= recipe_duration(order.recipe);
+        if order.paused ||
+            busy ||
+            !world.contains(stationary) ||
+            !order_wants_batch(world, &order)
+        {
+            continue;
+        }
+        let exist_rsrcs = what_we_have(world);
+        if can_order_batch(world, &tree, &researched, exist_rsrcs, order.recipe, 1).is_err() {
+            continue;
+        }
+        if let Ok(batch) = order_batch(world, resources, order.recipe, 1) {
+            if let Some(mut entry) = world.entry(batch) {
+                entry.add_component(FromOrder(order_entity));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::area::*;
+
+    fn ready_batch(world: &mut World, recipe: Recipe, amount: usize) -> Entity {
+        world.push((
+            ProductionBatch { recipe, amount },
+            ShiftsLeft(1),
+            TaskStatus::Ready,
+        ))
+    }
+
+    #[test]
+    fn finished_batch_goes_to_storage() {
+        let mut world = World::default();
+        let stock = install_germ(&mut world, Germ::GermT2, AreaType::Party);
+        if let Some(mut entry) = world.entry(stock) {
+            entry.add_component(TaskStatus::Ready);
+        }
+        ready_batch(&mut world, Recipe::GatherSlime, 2);
+        assert!(finish_batches(&mut world).is_empty());
+        assert_eq!(
+            how_much_we_have(&mut world, Resource::Slime),
+            scale_resources(recipe_outputs(Recipe::GatherSlime), 2)[&Resource::Slime],
+        );
+        assert!(batches_in_progress(&world).is_empty());
+    }
+
+    #[test]
+    fn output_without_storage_is_reported_lost() {
+        let mut world = World::default();
+        ready_batch(&mut world, Recipe::GatherSlime, 2);
+        assert_eq!(
+            finish_batches(&mut world),
+            scale_resources(recipe_outputs(Recipe::GatherSlime), 2),
+        );
+        assert!(batches_in_progress(&world).is_empty());
+    }
+}
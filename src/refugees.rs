@@ -0,0 +1,303 @@
+use legion::*;
+use rand::thread_rng;
+use rand::Rng;
+
+use crate::core::*;
+use crate::housing::*;
+use crate::mood::*;
+use crate::people::*;
+use crate::trust::*;
+
+/// Шанс самосбора в соседнем блоке за смену (из 100)
+pub static NEIGHBOUR_SAMOSBOR_CHANCE: usize = 3;
+
+/// Сколько смен беженцы ждут у гермы, прежде чем уйти
+pub static REFUGEE_WAIT_SHIFTS: usize = 3;
+
+/// Сколько смен люди помнят, что блок не пустил беженцев
+pub static REFUGEE_GUILT_SHIFTS: usize = 10;
+
+/// Сколько смен держится страх после самосбора у соседей
+pub static NEIGHBOUR_SAMOSBOR_FEAR: usize = 3;
+
+/// Шанс, что беженец болен (из 100). Заранее не узнать.
+pub static REFUGEE_SICK_CHANCE: u32 = 20;
+
+/// Группа беженцев у гермы. Профессии и разряды известны, здоровье - нет.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RefugeeGroup {
+    pub origin: String, // откуда пришли
+    pub members: Vec<(Profession, Tier)>,
+    pub shifts_left: usize, // сколько еще будут ждать
+}
+
+/// Кто сейчас ждет у гермы
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct PendingRefugees(pub Vec<RefugeeGroup>);
+
+/// Через сколько смен случится следующий самосбор у соседей
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NextNeighbourSamosbor(pub usize);
+
+impl Default for NextNeighbourSamosbor {
+    fn default() -> Self {
+        Self(neighbour_samosbor_delay())
+    }
+}
+
+/// Случайная задержка до самосбора у соседей, в среднем раз в 100 / NEIGHBOUR_SAMOSBOR_CHANCE смен
+pub fn neighbour_samosbor_delay() -> usize {
+    thread_rng().gen_range(1..=200 / NEIGHBOUR_SAMOSBOR_CHANCE)
+}
+
+/// Как блок обходился с беженцами
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct RefugeeRecord {
+    pub accepted: usize, // сколько человек принято
+    pub refused: usize, // скольким отказано, включая тех, кто не дождался
+    pub overfilled: usize, // скольких пришлось селить сверх вместимости
+    pub guilt: usize, // сколько смен еще помнят об отказе
+}
+
+/// Как отказ беженцам влияет на настроение
+pub fn refugees_mood_factor(
+    record: RefugeeRecord,
+) -> i8 {
+    if record.guilt > 0 { -1 } else { 0 }
+}
+
+/// Сгенерировать группу беженцев после самосбора в соседнем блоке
+pub fn random_refugee_group() -> RefugeeGroup {
+    let mut rng = thread_rng();
+    let profs = all_professions();
+    let size = rng.gen_range(2..9);
+    let members = (0..size)
+        .map(|_| {
+            let prof = profs[rng.gen_range(0..profs.len())];
+            let tier = match rng.gen_range(0..10) {
+                0 => Tier::T3,
+                1..=3 => Tier::T2,
+                _ => Tier::T1,
+            };
+            (prof, tier)
+        })
+        .collect();
+    RefugeeGroup {
+        origin: format!("Блок №{}", rng.gen_range(100..1000)),
+        members,
+        shifts_left: REFUGEE_WAIT_SHIFTS,
+    }
+}
+
+/// Поселить беженца. Сначала ищем место в наименее тесной жилячейке,
+/// если места нет нигде - подселяем сверх нормы.
+/// Возвращает комнату и пришлось ли переполнять.
+fn house_refugee(
+    world: &mut World,
+) -> Option<(Entity, bool)> {
+    if let Some(room) = least_crowded_living_room(world, COMRAD_RENTED_PLACE) {
+        return Some((room, false));
+    }
+    let mut rooms: Vec<(Entity, f32)> = living_rooms_crowding(world)
+        .into_iter()
+        .map(|(room, (capacity, occupied))| (room, crowding(occupied, capacity)))
+        .collect();
+    rooms.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+    rooms.first().map(|(room, _)| (*room, true))
+}
+
+/// Впустить группу беженцев.
+/// Партия недовольна, если пришлось селить в тесноте или поселили не всех.
+/// Возвращает, скольких удалось поселить.
+pub fn accept_refugees(
+    world: &mut World,
+    resources: &mut Resources,
+    group: RefugeeGroup,
+) -> usize {
+    let mut rng = thread_rng();
+    let mut record = resources
+        .get::<RefugeeRecord>()
+        .map(|r| *r)
+        .unwrap_or_default();
+    let overfilled_before = record.overfilled;
+    let mut housed = 0;
+    for (prof, tier) in group.members.iter() {
+        let (room, overfilled) = match house_refugee(world) {
+            Some(found) => found,
+            None => break,
+        };
+        let comrad = spawn_comrad(world, *prof, *tier, room);
+        if let Some(mut entry) = world.entry(comrad) {
+            entry.add_component(Satiety(rng.gen_range(60..150)));
+            if rng.gen_ratio(REFUGEE_SICK_CHANCE, 100) {
+                entry.add_component(Sick(SICK_SHIFTS));
+            }
+        }
+        record_history(world, comrad, format!("Беженец из блока {}", group.origin));
+        housed += 1;
+        if overfilled {
+            record.overfilled += 1;
+        }
+    }
+    let overfilled = record.overfilled - overfilled_before;
+    let refused = group.members.len() - housed;
+    record.accepted += housed;
+    record.refused += refused;
+    resources.insert(record);
+    report(resources, format!("Приняты беженцы из {}: {} чел", group.origin, housed));
+    change_trust(
+        resources,
+        -((overfilled * TRUST_PER_OVERFILLED as usize + refused * TRUST_PER_REFUSED as usize)
+            .min(TRUST_MAX as usize) as i16),
+        "беженцев поселили в тесноте или не всех",
+    );
+    housed
+}
+
+/// Отказать группе беженцев. Партия такого не одобряет.
+pub fn refuse_refugees(
+    resources: &mut Resources,
+    group: RefugeeGroup,
+) {
+    let mut record = resources
+        .get::<RefugeeRecord>()
+        .map(|r| *r)
+        .unwrap_or_default();
+    record.refused += group.members.len();
+    record.guilt = REFUGEE_GUILT_SHIFTS;
+    resources.insert(record);
+    report(resources, format!("Беженцам из {} отказано", group.origin));
+    change_trust(
+        resources,
+        -((group.members.len() * TRUST_PER_REFUSED as usize) as i16),
+        "отказ беженцам",
+    );
+}
+
+/// Беженцы за смену: самосборы у соседей приводят новых,
+/// не дождавшиеся уходят, память об отказах понемногу стирается.
+pub fn refugees_tick(
+    resources: &mut Resources,
+) {
+    let mut pending = resources
+        .get::<PendingRefugees>()
+        .map(|p| p.clone())
+        .unwrap_or_default();
+    let mut record = resources
+        .get::<RefugeeRecord>()
+        .map(|r| *r)
+        .unwrap_or_default();
+    record.guilt = record.guilt.saturating_sub(1);
+
+    let mut gone = Vec::new();
+    for group in pending.0.iter_mut() {
+        group.shifts_left = group.shifts_left.saturating_sub(1);
+        if group.shifts_left == 0 {
+            gone.push(group.clone());
+        }
+    }
+    pending.0.retain(|group| group.shifts_left > 0);
+    for group in gone.iter() {
+        record.refused += group.members.len();
+        record.guilt = REFUGEE_GUILT_SHIFTS;
+        report(resources, format!("Беженцы из {} не дождались и ушли", group.origin));
+        change_trust(
+            resources,
+            -((group.members.len() * TRUST_PER_REFUSED as usize) as i16),
+            "беженцы ушли от гермы",
+        );
+    }
+
+    let next = resources
+        .get::<NextNeighbourSamosbor>()
+        .map(|n| *n)
+        .unwrap_or_default();
+    let next = NextNeighbourSamosbor(next.0.saturating_sub(1));
+    if next.0 > 0 {
+        resources.insert(next);
+    } else {
+        resources.insert(NextNeighbourSamosbor::default());
+        let group = random_refugee_group();
+        report(resources, format!(
+            "Самосбор в соседнем блоке! У гермы беженцы из {}: {} чел",
+            group.origin,
+            group.members.len(),
+        ));
+        pending.0.push(group);
+        let fear = resources
+            .get::<SamosborFear>()
+            .map(|f| *f)
+            .unwrap_or_default();
+        resources.insert(SamosborFear(fear.0.max(NEIGHBOUR_SAMOSBOR_FEAR)));
+    }
+
+    resources.insert(pending);
+    resources.insert(record);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(size: usize) -> RefugeeGroup {
+        RefugeeGroup {
+            origin: "Блок №404".to_string(),
+            members: vec![(Profession::Worker, Tier::T1); size],
+            shifts_left: REFUGEE_WAIT_SHIFTS,
+        }
+    }
+
+    #[test]
+    fn refusal_costs_trust_and_mood() {
+        let mut resources = Resources::default();
+        refuse_refugees(&mut resources, group(3));
+        let record = *resources.get::<RefugeeRecord>().unwrap();
+        assert_eq!(record.refused, 3);
+        assert_eq!(record.guilt, REFUGEE_GUILT_SHIFTS);
+        assert_eq!(refugees_mood_factor(record), -1);
+        assert_eq!(*resources.get::<PartyTrust>().unwrap(), PartyTrust(TRUST_START - 3 * TRUST_PER_REFUSED));
+    }
+
+    #[test]
+    fn no_housing_means_nobody_housed() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        assert_eq!(accept_refugees(&mut world, &mut resources, group(2)), 0);
+        let record = *resources.get::<RefugeeRecord>().unwrap();
+        assert_eq!(record.accepted, 0);
+        assert_eq!(record.refused, 2);
+        assert_eq!(*resources.get::<PartyTrust>().unwrap(), PartyTrust(TRUST_START - 2 * TRUST_PER_REFUSED));
+    }
+
+    #[test]
+    fn refugees_leave_if_kept_waiting() {
+        let mut resources = Resources::default();
+        resources.insert(NextNeighbourSamosbor(100));
+        resources.insert(PendingRefugees(vec![group(2)]));
+        for _ in 1..REFUGEE_WAIT_SHIFTS {
+            refugees_tick(&mut resources);
+            assert_eq!(resources.get::<PendingRefugees>().unwrap().0.len(), 1);
+        }
+        refugees_tick(&mut resources);
+        assert!(resources.get::<PendingRefugees>().unwrap().0.is_empty());
+        let record = *resources.get::<RefugeeRecord>().unwrap();
+        assert_eq!(record.refused, 2);
+        assert_eq!(record.guilt, REFUGEE_GUILT_SHIFTS);
+        refugees_tick(&mut resources);
+        assert_eq!(resources.get::<RefugeeRecord>().unwrap().guilt, REFUGEE_GUILT_SHIFTS - 1);
+    }
+
+    #[test]
+    fn neighbour_samosbor_brings_refugees_and_fear() {
+        let mut resources = Resources::default();
+        resources.insert(NextNeighbourSamosbor(1));
+        refugees_tick(&mut resources);
+        let pending = resources.get::<PendingRefugees>().unwrap().clone();
+        assert_eq!(pending.0.len(), 1);
+        assert_eq!(pending.0[0].shifts_left, REFUGEE_WAIT_SHIFTS);
+        let fear = *resources.get::<SamosborFear>().unwrap();
+        assert_eq!(fear, SamosborFear(NEIGHBOUR_SAMOSBOR_FEAR));
+        assert_eq!(samosbor_mood_factor(fear), -2);
+        assert_eq!(samosbor_mood_factor(SamosborFear(0)), 0);
+    }
+}
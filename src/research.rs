@@ -0,0 +1,382 @@
+use std::fmt;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use legion::*;
+use serde::Deserialize;
+
+use crate::content::*;
+use crate::core::*;
+use crate::people::*;
+use crate::production::*;
+use crate::recipes::*;
+use crate::resources::*;
+use crate::storage::*;
+
+/// Что дает исследование
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum Unlock {
+    Recipe(Recipe), // новый техпроцесс
+    Stationary(Stationary), // новое оборудование
+    Germ(Germ), // новый гермокомплект
+    SamosborForecast, // прогноз самосборов у соседей
+}
+
+impl fmt::Display for Unlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Unlock::Recipe(recipe) => write!(f, "Техпроцесс: {}", recipe),
+            Unlock::Stationary(stationary) => write!(f, "Оборудование: {}", stationary),
+            Unlock::Germ(germ) => write!(f, "Гермокомплект: {}", germ),
+            Unlock::SamosborForecast => write!(f, "Прогноз самосборов"),
+        }
+    }
+}
+
+/// Исследование из дерева технологий
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Tech {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub requires: Vec<String>, // какие исследования нужны до этого
+    pub lab: Stationary, // в какой лаборатории идет
+    pub tier: Tier, // какого разряда нужен ученый
+    pub bp: usize, // сколько трудочасов ученых
+    pub reagents: HashMap<Resource, usize>, // сколько реактивов уходит
+    pub unlocks: Vec<Unlock>,
+}
+
+/// Дерево технологий
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct TechTree(pub Vec<Tech>);
+
+impl TechTree {
+    pub fn get(&self, id: &str) -> Option<&Tech> {
+        self.0.iter().find(|tech| tech.id == id)
+    }
+}
+
+/// Что уже исследовано
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Researched(pub HashSet<String>);
+
+/// Исследовательский проект. Идет как задание для ученых на лабораторном оборудовании.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResearchProject(pub String);
+
+/// Где лежит файл с деревом технологий
+pub static TECH_TREE_PATH: &str = "data/research.ron";
+
+/// Дерево технологий, вшитое в сборку
+static TECH_TREE_EMBEDDED: &str = include_str!("../data/research.ron");
+
+/// Разобрать дерево технологий из текста
+pub fn parse_tech_tree(
+    text: &str,
+) -> Result<TechTree, String> {
+    ron::de::from_str(text)
+        .map(TechTree)
+        .map_err(|e| e.to_string())
+}
+
+/// Проверить дерево: id уникальны, требования ссылаются на существующие
+/// исследования, циклов нет, проекты идут в лабораториях и чего-то стоят.
+pub fn validate_tech_tree(
+    tree: &TechTree,
+) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut ids = HashSet::new();
+    for tech in tree.0.iter() {
+        if !ids.insert(tech.id.as_str()) {
+            errors.push(format!("{}: id повторяется", tech.id));
+        }
+        for req in tech.requires.iter() {
+            if tree.get(req).is_none() {
+                errors.push(format!("{}: требует неизвестное исследование {}", tech.id, req));
+            }
+        }
+        if ![Stationary::LabT1, Stationary::LabT2, Stationary::LabT3].contains(&tech.lab) {
+            errors.push(format!("{}: lab должен быть лабораторией, а не {:?}", tech.id, tech.lab));
+        }
+        if tech.bp == 0 {
+            errors.push(format!("{}: bp должен быть больше 0", tech.id));
+        }
+        if tech.unlocks.is_empty() {
+            errors.push(format!("{}: исследование ничего не открывает", tech.id));
+        }
+    }
+    // исследование с циклом в требованиях никогда не станет доступно
+    let mut resolved: HashSet<&str> = HashSet::new();
+    loop {
+        let ready: Vec<&str> = tree.0
+            .iter()
+            .filter(|tech| !resolved.contains(tech.id.as_str()))
+            .filter(|tech| tech.requires.iter().all(|req| resolved.contains(req.as_str())))
+            .map(|tech| tech.id.as_str())
+            .collect();
+        if ready.is_empty() {
+            break;
+        }
+        resolved.extend(ready);
+    }
+    for tech in tree.0.iter() {
+        if !resolved.contains(tech.id.as_str()) && tech.requires.iter().all(|req| tree.get(req).is_some()) {
+            errors.push(format!("{}: недостижимо, цикл в требованиях", tech.id));
+        }
+    }
+    errors
+}
+
+/// Загрузить и проверить дерево технологий. С битым деревом игра не запускается.
+pub fn load_tech_tree() -> TechTree {
+    let text = read_data_file(TECH_TREE_PATH, TECH_TREE_EMBEDDED);
+    let tree = parse_tech_tree(&text)
+        .unwrap_or_else(|e| panic!("{}: не удалось разобрать дерево технологий: {}", TECH_TREE_PATH, e));
+    let errors = validate_tech_tree(&tree);
+    if !errors.is_empty() {
+        panic!("{}: ошибки в дереве технологий:\n{}", TECH_TREE_PATH, errors.join("\n"));
+    }
+    tree
+}
+
+/// Доступна ли штука. Все, что не открывается исследованиями, доступно сразу.
+pub fn is_unlocked(
+    tree: &TechTree,
+    researched: &Researched,
+    unlock: Unlock,
+) -> bool {
+    let mut unlocked_by = tree
+        .0
+        .iter()
+        .filter(|tech| tech.unlocks.contains(&unlock))
+        .peekable();
+    if unlocked_by.peek().is_none() {
+        return true;
+    }
+    unlocked_by.any(|tech| researched.0.contains(&tech.id))
+}
+
+/// Какие исследования надо провести, чтобы открыть штуку.
+/// Пусто, если она уже доступна.
+pub fn missing_research(
+    tree: &TechTree,
+    researched: &Researched,
+    unlock: Unlock,
+) -> Vec<String> {
+    if is_unlocked(tree, researched, unlock) {
+        return Vec::new();
+    }
+    tree.0
+        .iter()
+        .filter(|tech| tech.unlocks.contains(&unlock))
+        .map(|tech| tech.name.clone())
+        .collect()
+}
+
+/// Трудозатраты на исследование
+pub fn research_requirements(
+    tech: &Tech,
+) -> HashSet<TaskMeta> {
+    [
+        TaskMeta {
+            prof: Profession::Scientist,
+            tier: tech.tier,
+            bp: BuildPower(tech.bp),
+            stationary: tech.lab,
+        },
+    ].iter().cloned().collect()
+}
+
+/// Расход реактивов на исследование
+pub fn research_cost(
+    tech: &Tech,
+) -> HashMap<Resource, RealUnits> {
+    tech.reagents
+        .iter()
+        .map(|(res, amount)| (*res, RealUnits(*amount)))
+        .collect()
+}
+
+/// Какие исследования сейчас идут: проект и прогресс
+pub fn research_in_progress(
+    world: &World,
+) -> HashMap<String, Option<TaskProgress>> {
+    let mut query = <(&ResearchProject, Option<&TaskProgress>)>::query();
+    query
+        .iter(world)
+        .map(|(project, progress)| (project.0.clone(), progress.cloned()))
+        .collect()
+}
+
+/// Можно ли начать исследование: все предыдущие исследованы,
+/// само еще не исследовано и не идет
+pub fn can_start_research(
+    world: &World,
+    tree: &TechTree,
+    researched: &Researched,
+    id: &str,
+) -> bool {
+    let tech = match tree.get(id) {
+        Some(tech) => tech,
+        None => return false,
+    };
+    !researched.0.contains(id)
+        && !research_in_progress(world).contains_key(id)
+        && tech.requires.iter().all(|req| researched.0.contains(req))
+}
+
+/// Начать исследование. Реактивы списываются сразу.
+pub fn start_research(
+    world: &mut World,
+    resources: &Resources,
+    id: &str,
+) -> Result<Entity, SamosborError> {
+    let tree = resources
+        .get::<TechTree>()
+        .map(|t| t.clone())
+        .unwrap_or_default();
+    let researched = resources
+        .get::<Researched>()
+        .map(|r| r.clone())
+        .unwrap_or_default();
+    if !can_start_research(world, &tree, &researched, id) {
+        return Err(SamosborError::ResearchUnavailable);
+    }
+    let tech = tree.get(id).unwrap();
+    writeoff_bunch(world, research_cost(tech))?;
+    Ok(world.push((
+        ResearchProject(tech.id.clone()),
+        TaskPriority(0),
+        TaskStatus::Constructing,
+        task_meta2progress(research_requirements(tech)),
+    )))
+}
+
+/// Завершить готовые исследования и записать их в исследованное
+pub fn finish_research(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    let mut query = <(Entity, &ResearchProject, &TaskStatus)>::query();
+    let finished: Vec<(Entity, String)> = query
+        .iter(world)
+        .filter(|(_, _, status)| **status == TaskStatus::Ready)
+        .map(|(e, project, _)| (*e, project.0.clone()))
+        .collect();
+    if finished.is_empty() {
+        return;
+    }
+    let mut researched = resources
+        .get::<Researched>()
+        .map(|r| r.clone())
+        .unwrap_or_default();
+    let tree = resources
+        .get::<TechTree>()
+        .map(|t| t.clone())
+        .unwrap_or_default();
+    for (entity, id) in finished {
+        world.remove(entity);
+        let name = tree
+            .get(&id)
+            .map(|tech| tech.name.clone())
+            .unwrap_or_else(|| id.clone());
+        researched.0.insert(id);
+        report(resources, format!("Исследование завершено: {}", name));
+    }
+    resources.insert(researched);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tree() -> TechTree {
+        parse_tech_tree(r#"[
+            (
+                id: "metallurgy",
+                name: "Обработка цветмета",
+                description: "",
+                requires: [],
+                lab: LabT1,
+                tier: T1,
+                bp: 10,
+                reagents: {},
+                unlocks: [Recipe(ComponentT2)],
+            ),
+            (
+                id: "labs",
+                name: "Лабораторное дело",
+                description: "",
+                requires: ["metallurgy"],
+                lab: LabT1,
+                tier: T1,
+                bp: 10,
+                reagents: {},
+                unlocks: [Stationary(LabT2)],
+            ),
+        ]"#).unwrap()
+    }
+
+    fn researched(ids: &[&str]) -> Researched {
+        Researched(ids.iter().map(|id| id.to_string()).collect())
+    }
+
+    #[test]
+    fn unlocks_need_research() {
+        let tree = tree();
+        let none = researched(&[]);
+        assert!(validate_tech_tree(&tree).is_empty());
+        assert!(!is_unlocked(&tree, &none, Unlock::Recipe(Recipe::ComponentT2)));
+        assert_eq!(
+            missing_research(&tree, &none, Unlock::Recipe(Recipe::ComponentT2)),
+            vec!["Обработка цветмета".to_string()],
+        );
+        // что не открывается исследованием, доступно сразу
+        assert!(is_unlocked(&tree, &none, Unlock::Recipe(Recipe::ComponentT1)));
+        let done = researched(&["metallurgy"]);
+        assert!(is_unlocked(&tree, &done, Unlock::Recipe(Recipe::ComponentT2)));
+        assert!(missing_research(&tree, &done, Unlock::Recipe(Recipe::ComponentT2)).is_empty());
+    }
+
+    #[test]
+    fn research_needs_prerequisites() {
+        let world = World::default();
+        let tree = tree();
+        assert!(can_start_research(&world, &tree, &researched(&[]), "metallurgy"));
+        assert!(!can_start_research(&world, &tree, &researched(&[]), "labs"));
+        assert!(can_start_research(&world, &tree, &researched(&["metallurgy"]), "labs"));
+        assert!(!can_start_research(&world, &tree, &researched(&["metallurgy"]), "metallurgy"));
+        assert!(!can_start_research(&world, &tree, &researched(&[]), "unknown"));
+    }
+
+    #[test]
+    fn research_runs_once_and_unlocks() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(tree());
+        assert_eq!(start_research(&mut world, &resources, "labs"), Err(SamosborError::ResearchUnavailable));
+        let project = start_research(&mut world, &resources, "metallurgy").unwrap();
+        assert_eq!(start_research(&mut world, &resources, "metallurgy"), Err(SamosborError::ResearchUnavailable));
+        assert_eq!(order_batch(&mut world, &resources, Recipe::ComponentT2, 1), Err(SamosborError::ResearchUnavailable));
+
+        if let Some(mut entry) = world.entry(project) {
+            entry.add_component(TaskStatus::Ready);
+        }
+        finish_research(&mut world, &mut resources);
+        assert!(research_in_progress(&world).is_empty());
+        assert!(resources.get::<Researched>().unwrap().0.contains("metallurgy"));
+        assert_ne!(order_batch(&mut world, &resources, Recipe::ComponentT2, 1), Err(SamosborError::ResearchUnavailable));
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let mut tree = tree();
+        tree.0[0].requires = vec!["labs".to_string()];
+        let errors = validate_tech_tree(&tree);
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|e| e.contains("цикл")));
+    }
+}
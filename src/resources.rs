@@ -1,7 +1,8 @@
 use std::{fmt, hash::Hash};
+use serde::Deserialize;
 
 #[allow(dead_code)]
-#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash, PartialOrd, Ord)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Hash, PartialOrd, Ord, Deserialize)]
 pub enum Resource {
     Concentrat, // пищевой концентрат
 
@@ -48,20 +49,20 @@ pub fn all_resources () -> Vec<Resource> {
 impl fmt::Display for Resource {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Resource::BioRaw           => write!(f, "{}", "Биосырье"),
-            Resource::ScrapT1          => write!(f, "{}", "Чермет"),
-            Resource::ScrapT2          => write!(f, "{}", "Цветмет"),
-            Resource::ScrapT3          => write!(f, "{}", "Редкие металлы"),
-            Resource::Slime => write!(f, "{}", "Слизь"),
-            Resource::ComponentT1      => write!(f, "{}", "Механический компонент"),
-            Resource::ComponentT2      => write!(f, "{}", "Электронный компонент"),
-            Resource::ComponentT3      => write!(f, "{}", "Суперкомпонент"),
-            Resource::Concrete      => write!(f, "{}", "Бетон"),
-            Resource::ReagentT1        => write!(f, "{}", "Экоцид"),
-            Resource::ReagentT2        => write!(f, "{}", "Компониум"),
-            Resource::ReagentT3        => write!(f, "{}", "Сталий"),
-            Resource::Polymer        => write!(f, "{}", "Полимер"),
-            Resource::Concentrat     => write!(f, "{}", "Пищевой концентрат"),
+            Resource::BioRaw           => write!(f, "Биосырье"),
+            Resource::ScrapT1          => write!(f, "Чермет"),
+            Resource::ScrapT2          => write!(f, "Цветмет"),
+            Resource::ScrapT3          => write!(f, "Редкие металлы"),
+            Resource::Slime => write!(f, "Слизь"),
+            Resource::ComponentT1      => write!(f, "Механический компонент"),
+            Resource::ComponentT2      => write!(f, "Электронный компонент"),
+            Resource::ComponentT3      => write!(f, "Суперкомпонент"),
+            Resource::Concrete      => write!(f, "Бетон"),
+            Resource::ReagentT1        => write!(f, "Экоцид"),
+            Resource::ReagentT2        => write!(f, "Компониум"),
+            Resource::ReagentT3        => write!(f, "Сталий"),
+            Resource::Polymer        => write!(f, "Полимер"),
+            Resource::Concentrat     => write!(f, "Пищевой концентрат"),
         }
     }
 }
@@ -0,0 +1,533 @@
+use std::fmt;
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
+use legion::*;
+
+use crate::core::*;
+use crate::people::*;
+
+/// Роль бойца в отряде
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SquadRole {
+    Sergeant, // Командир отряда
+    Flamethrower, // Огнеметчик
+    Raker, // Граблист
+    Spark, // Наряд с искрой
+    Granit, // Расчет Гранита
+    Rifleman, // Стрелок
+}
+
+impl fmt::Display for SquadRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SquadRole::Sergeant     => write!(f, "Сержант"),
+            SquadRole::Flamethrower => write!(f, "Огнеметчик"),
+            SquadRole::Raker        => write!(f, "Граблист"),
+            SquadRole::Spark        => write!(f, "Искровик"),
+            SquadRole::Granit       => write!(f, "Гранитчик"),
+            SquadRole::Rifleman     => write!(f, "Стрелок"),
+        }
+    }
+}
+
+/// Штатный стандарт отряда ОЛПС
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SquadStandard {
+    Standard1G, // №1-Ж. Полный отряд зачистки.
+    Standard2G, // №2-Ж. Облегченный отряд зачистки.
+    Patrol, // Патруль. Дежурство по этажу.
+}
+
+impl fmt::Display for SquadStandard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SquadStandard::Standard1G => write!(f, "№1-Ж"),
+            SquadStandard::Standard2G => write!(f, "№2-Ж"),
+            SquadStandard::Patrol     => write!(f, "Патруль"),
+        }
+    }
+}
+
+/// FIXME: надо генерить список напрямую из энума.
+pub fn all_squad_standards() -> Vec<SquadStandard> {
+    vec![
+        SquadStandard::Standard1G,
+        SquadStandard::Standard2G,
+        SquadStandard::Patrol,
+    ]
+}
+
+/// Штатный состав отряда: сколько бойцов какой роли и разряда нужно
+pub fn standard_composition(
+    standard: SquadStandard,
+) -> HashMap<SquadRole, (Tier, usize)> {
+    match standard {
+        SquadStandard::Standard1G => [
+            (SquadRole::Sergeant, (Tier::T2, 1)),
+            (SquadRole::Flamethrower, (Tier::T1, 5)),
+            (SquadRole::Raker, (Tier::T1, 4)),
+            (SquadRole::Spark, (Tier::T1, 2)),
+            (SquadRole::Granit, (Tier::T1, 2)),
+        ].iter().cloned().collect(),
+        SquadStandard::Standard2G => [
+            (SquadRole::Sergeant, (Tier::T2, 1)),
+            (SquadRole::Flamethrower, (Tier::T1, 2)),
+            (SquadRole::Raker, (Tier::T1, 2)),
+            (SquadRole::Spark, (Tier::T1, 1)),
+        ].iter().cloned().collect(),
+        SquadStandard::Patrol => [
+            (SquadRole::Sergeant, (Tier::T1, 1)),
+            (SquadRole::Rifleman, (Tier::T1, 2)),
+        ].iter().cloned().collect(),
+    }
+}
+
+/// Задание отряда
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Mission {
+    Idle, // В казарме
+    Patrol, // Патрулирование этажей
+    Cleanup, // Зачистка после самосбора
+}
+
+impl fmt::Display for Mission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mission::Idle    => write!(f, "В казарме"),
+            Mission::Patrol  => write!(f, "Патрулирование"),
+            Mission::Cleanup => write!(f, "Зачистка"),
+        }
+    }
+}
+
+/// Сколько смен длится задание
+pub fn mission_duration(
+    mission: Mission,
+) -> usize {
+    match mission {
+        Mission::Idle => 0,
+        Mission::Patrol => 1,
+        Mission::Cleanup => 3,
+    }
+}
+
+/// Отряд. Сущность, к которой приписаны бойцы.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Squad {
+    pub name: String,
+    pub standard: SquadStandard,
+}
+
+/// Командир отряда
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SquadCommander(pub Entity);
+
+/// Текущее задание отряда и сколько смен до его окончания
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SquadMission {
+    pub mission: Mission,
+    pub shifts_left: usize,
+}
+
+/// Метка того, в каком отряде и в какой роли состоит боец
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InSquad {
+    pub squad: Entity,
+    pub role: SquadRole,
+}
+
+/// Сформировать пустой отряд
+pub fn create_squad(
+    world: &mut World,
+    name: String,
+    standard: SquadStandard,
+) -> Entity {
+    world.push((
+        Squad { name, standard },
+        SquadMission {
+            mission: Mission::Idle,
+            shifts_left: 0,
+        },
+    ))
+}
+
+/// Приписать бойца к отряду.
+/// Сержант становится командиром отряда.
+pub fn assign_to_squad(
+    world: &mut World,
+    squad: Entity,
+    comrad: Entity,
+    role: SquadRole,
+) {
+    if let Some(mut entry) = world.entry(comrad) {
+        entry.add_component(InSquad { squad, role });
+    }
+    let name = world
+        .entry_ref(squad)
+        .ok()
+        .and_then(|entry| entry.get_component::<Squad>().ok().map(|s| s.name.clone()))
+        .unwrap_or_default();
+    record_history(world, comrad, format!("Зачисление в отряд \"{}\": {}", name, role));
+    if role == SquadRole::Sergeant {
+        if let Some(mut entry) = world.entry(squad) {
+            entry.add_component(SquadCommander(comrad));
+        }
+    }
+}
+
+/// Вывести бойца из отряда. Если уходит командир, командует другой сержант отряда.
+pub fn leave_squad(
+    world: &mut World,
+    comrad: Entity,
+) {
+    let in_squad = world
+        .entry_ref(comrad)
+        .ok()
+        .and_then(|entry| entry.get_component::<InSquad>().ok().copied());
+    if in_squad.is_none() {
+        return;
+    }
+    if let Some(mut entry) = world.entry(comrad) {
+        entry.remove_component::<InSquad>();
+    }
+    record_history(world, comrad, "Отчисление из отряда".to_string());
+    update_commanders(world);
+}
+
+/// Переназначить командиров отрядов, чей командир умер, сбежал или ушел из отряда.
+/// Командование принимает старший по разряду сержант отряда, если такой есть.
+pub fn update_commanders(
+    world: &mut World,
+) {
+    let mut squads_query = <(Entity, Option<&SquadCommander>)>::query()
+        .filter(component::<Squad>());
+    let squads: Vec<(Entity, Option<Entity>)> = squads_query
+        .iter(world)
+        .map(|(squad, commander)| (*squad, commander.map(|SquadCommander(c)| *c)))
+        .collect();
+    for (squad, commander) in squads {
+        let sergeants: Vec<(Tier, Entity)> = squad_members(world, squad)
+            .into_iter()
+            .filter(|(role, _, _)| *role == SquadRole::Sergeant)
+            .map(|(_, tier, comrad)| (tier, comrad))
+            .collect();
+        if commander.map(|c| sergeants.iter().any(|(_, s)| *s == c)).unwrap_or(false) {
+            continue;
+        }
+        let successor = sergeants.iter().max_by_key(|(tier, _)| *tier).map(|(_, s)| *s);
+        if let Some(mut entry) = world.entry(squad) {
+            match successor {
+                Some(sergeant) => entry.add_component(SquadCommander(sergeant)),
+                None => entry.remove_component::<SquadCommander>(),
+            }
+        }
+    }
+}
+
+/// Ликвидаторы, которые пока не приписаны ни к одному отряду
+pub fn free_likvidators(
+    world: &World,
+) -> Vec<(Tier, Entity)> {
+    let mut query = <(Entity, &Profession, &Tier)>::query()
+        .filter(!component::<InSquad>());
+    let mut result: Vec<(Tier, Entity)> = query
+        .iter(world)
+        .filter(|(_, prof, _)| **prof == Profession::Likvidator)
+        .map(|(entity, _, tier)| (*tier, *entity))
+        .collect();
+    // сначала ставим самых неопытных, опытные пригодятся на командных местах
+    result.sort_by_key(|(tier, _)| *tier);
+    result
+}
+
+/// Сформировать отряд по стандарту из свободных ликвидаторов.
+/// Отряд создается, только если людей хватает на весь штат.
+pub fn form_squad(
+    world: &mut World,
+    standard: SquadStandard,
+) -> Result<Entity, SamosborError> {
+    let mut free = free_likvidators(world);
+    let mut composition: Vec<(SquadRole, (Tier, usize))> = standard_composition(standard)
+        .into_iter()
+        .collect();
+    // самые требовательные роли набираем первыми
+    composition.sort_by(|(r1, (t1, _)), (r2, (t2, _))| t2.cmp(t1).then(r1.cmp(r2)));
+    let mut picked = Vec::new();
+    for (role, (tier, count)) in composition {
+        for _ in 0..count {
+            match free.iter().position(|(t, _)| *t >= tier) {
+                Some(i) => picked.push((role, free.remove(i).1)),
+                None => return Err(SamosborError::SquadIncomplete),
+            }
+        }
+    }
+    let mut query = <&Squad>::query();
+    let number = query.iter(world).count() + 1;
+    let squad = create_squad(world, format!("Расчет №{}", number), standard);
+    for (role, comrad) in picked {
+        assign_to_squad(world, squad, comrad, role);
+    }
+    Ok(squad)
+}
+
+/// Кто сейчас числится в отряде: роль -> (разряд, боец).
+/// Бойцом считается только ликвидатор: переученный в другую профессию
+/// числится в отряде, но штатное место не занимает.
+pub fn squad_members(
+    world: &World,
+    squad: Entity,
+) -> Vec<(SquadRole, Tier, Entity)> {
+    let mut result = Vec::new();
+    let mut query = <(Entity, &InSquad, &Profession, &Tier)>::query();
+    for (entity, in_squad, _, tier) in query
+        .iter(world)
+        .filter(|(_, in_squad, prof, _)| in_squad.squad == squad && **prof == Profession::Likvidator)
+    {
+        result.push((in_squad.role, *tier, *entity));
+    }
+    result.sort_by_key(|(role, _, _)| *role);
+    result
+}
+
+/// Соответствует ли отряд своему стандарту.
+/// В случае некомплекта возвращает сколько бойцов какой роли не хватает.
+/// Штатные места занимают только ликвидаторы,
+/// боец высокого разряда может занимать место бойца низкого.
+pub fn validate_squad(
+    world: &World,
+    squad: Entity,
+) -> Result<(), HashMap<SquadRole, usize>> {
+    let standard = match world
+        .entry_ref(squad)
+        .ok()
+        .and_then(|entry| entry.get_component::<Squad>().ok().cloned())
+    {
+        Some(s) => s.standard,
+        None => return Err(HashMap::new()),
+    };
+    let members = squad_members(world, squad);
+    let mut missing = HashMap::new();
+    for (role, (tier, count)) in standard_composition(standard) {
+        let have = members
+            .iter()
+            .filter(|(r, t, _)| *r == role && *t >= tier)
+            .count();
+        if have < count {
+            missing.insert(role, count - have);
+        }
+    }
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(missing)
+    }
+}
+
+/// Отправить отряд на задание.
+/// Неукомплектованный или уже занятый отряд на задание не отправить.
+pub fn dispatch_squad(
+    world: &mut World,
+    squad: Entity,
+    mission: Mission,
+) -> Result<(), SamosborError> {
+    let busy = world
+        .entry_ref(squad)
+        .ok()
+        .and_then(|entry| entry.get_component::<SquadMission>().ok().copied())
+        .map(|m| m.mission != Mission::Idle)
+        .unwrap_or(true);
+    if busy {
+        return Err(SamosborError::SquadBusy);
+    }
+    if validate_squad(world, squad).is_err() {
+        return Err(SamosborError::SquadIncomplete);
+    }
+    if let Some(mut entry) = world.entry(squad) {
+        entry.add_component(SquadMission {
+            mission,
+            shifts_left: mission_duration(mission),
+        });
+    }
+    Ok(())
+}
+
+/// Бойцы отрядов, которые сейчас на задании
+pub fn fighters_on_mission(
+    world: &World,
+) -> HashSet<Entity> {
+    let mut on_mission = HashSet::new();
+    let mut squads_query = <(Entity, &SquadMission)>::query();
+    for (squad, mission) in squads_query.iter(world) {
+        if mission.mission != Mission::Idle {
+            on_mission.insert(*squad);
+        }
+    }
+    let mut fighters_query = <(Entity, &InSquad)>::query();
+    fighters_query
+        .iter(world)
+        .filter(|(_, in_squad)| on_mission.contains(&in_squad.squad))
+        .map(|(entity, _)| *entity)
+        .collect()
+}
+
+/// Отряды возвращаются с заданий.
+/// Отряды, потерявшие командира, получают нового.
+pub fn missions_tick(
+    world: &mut World,
+) {
+    update_commanders(world);
+    let mut query = <&mut SquadMission>::query();
+    for sm in query.iter_mut(world) {
+        if sm.shifts_left > 0 {
+            sm.shifts_left -= 1;
+        }
+        if sm.shifts_left == 0 {
+            sm.mission = Mission::Idle;
+        }
+    }
+}
+
+/// Все отряды блока для отображения: отряд, название, стандарт,
+/// задание, численность, некомплект
+#[allow(clippy::type_complexity)]
+pub fn all_squads(
+    world: &World,
+) -> Vec<(Entity, String, SquadStandard, SquadMission, usize, HashMap<SquadRole, usize>)> {
+    let mut squads = Vec::new();
+    let mut query = <(Entity, &Squad, &SquadMission)>::query();
+    for (entity, squad, mission) in query.iter(world) {
+        squads.push((*entity, squad.clone(), *mission));
+    }
+    let mut result = Vec::new();
+    for (entity, squad, mission) in squads {
+        let members = squad_members(world, entity).len();
+        let missing = validate_squad(world, entity)
+            .err()
+            .unwrap_or_default();
+        result.push((entity, squad.name, squad.standard, mission, members, missing));
+    }
+    result.sort_by(|a, b| a.1.cmp(&b.1));
+    result
+}
+
+/// Отряд ликвидаторов ОЛПС по стандарту №1-Ж
+pub fn spawn_1_g (
+    world: &mut World,
+    room: Entity,
+) -> Entity {
+    let squad = create_squad(
+        world,
+        "Расчет №1".to_string(),
+        SquadStandard::Standard1G,
+    );
+    for (role, (tier, count)) in standard_composition(SquadStandard::Standard1G) {
+        for _ in 0..count {
+            let comrad = spawn_comrad(
+                world,
+                Profession::Likvidator,
+                tier,
+                room,
+            );
+            assign_to_squad(world, squad, comrad, role);
+        }
+    }
+    squad
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::area::*;
+    use crate::production::*;
+
+    fn barracks(world: &mut World) -> Entity {
+        install_germ(world, Germ::GermT2, AreaType::Military)
+    }
+
+    #[test]
+    fn full_squad_is_valid() {
+        let mut world = World::default();
+        let room = barracks(&mut world);
+        let squad = spawn_1_g(&mut world, room);
+        assert_eq!(validate_squad(&world, squad), Ok(()));
+        assert_eq!(dispatch_squad(&mut world, squad, Mission::Cleanup), Ok(()));
+        let staff: usize = standard_composition(SquadStandard::Standard1G)
+            .values()
+            .map(|(_, count)| count)
+            .sum();
+        assert_eq!(fighters_on_mission(&world).len(), staff);
+    }
+
+    #[test]
+    fn only_likvidators_fill_roles() {
+        let mut world = World::default();
+        let room = barracks(&mut world);
+        let squad = create_squad(&mut world, "Патруль".to_string(), SquadStandard::Patrol);
+        let worker = spawn_comrad(&mut world, Profession::Worker, Tier::T3, room);
+        assign_to_squad(&mut world, squad, worker, SquadRole::Sergeant);
+        for _ in 0..2 {
+            let rifleman = spawn_comrad(&mut world, Profession::Likvidator, Tier::T1, room);
+            assign_to_squad(&mut world, squad, rifleman, SquadRole::Rifleman);
+        }
+        let missing = validate_squad(&world, squad).unwrap_err();
+        assert_eq!(missing.get(&SquadRole::Sergeant), Some(&1));
+        assert_eq!(missing.get(&SquadRole::Rifleman), None);
+        assert_eq!(dispatch_squad(&mut world, squad, Mission::Patrol), Err(SamosborError::SquadIncomplete));
+    }
+
+    #[test]
+    fn form_squad_needs_full_staff() {
+        let mut world = World::default();
+        let room = barracks(&mut world);
+        spawn_comrad(&mut world, Profession::Likvidator, Tier::T1, room);
+        spawn_comrad(&mut world, Profession::Likvidator, Tier::T1, room);
+        assert_eq!(form_squad(&mut world, SquadStandard::Patrol), Err(SamosborError::SquadIncomplete));
+        spawn_comrad(&mut world, Profession::Worker, Tier::T1, room);
+        assert_eq!(form_squad(&mut world, SquadStandard::Patrol), Err(SamosborError::SquadIncomplete));
+        spawn_comrad(&mut world, Profession::Likvidator, Tier::T1, room);
+        let squad = form_squad(&mut world, SquadStandard::Patrol).unwrap();
+        assert_eq!(validate_squad(&world, squad), Ok(()));
+        assert!(free_likvidators(&world).is_empty());
+    }
+
+    #[test]
+    fn squad_returns_from_mission() {
+        let mut world = World::default();
+        let room = barracks(&mut world);
+        let squad = spawn_1_g(&mut world, room);
+        dispatch_squad(&mut world, squad, Mission::Cleanup).unwrap();
+        assert_eq!(dispatch_squad(&mut world, squad, Mission::Patrol), Err(SamosborError::SquadBusy));
+        for _ in 0..mission_duration(Mission::Cleanup) {
+            missions_tick(&mut world);
+        }
+        assert!(fighters_on_mission(&world).is_empty());
+        assert_eq!(dispatch_squad(&mut world, squad, Mission::Patrol), Ok(()));
+    }
+
+    #[test]
+    fn dead_commander_is_replaced() {
+        let mut world = World::default();
+        let room = barracks(&mut world);
+        let squad = create_squad(&mut world, "Патруль".to_string(), SquadStandard::Patrol);
+        let first = spawn_comrad(&mut world, Profession::Likvidator, Tier::T2, room);
+        let second = spawn_comrad(&mut world, Profession::Likvidator, Tier::T1, room);
+        assign_to_squad(&mut world, squad, first, SquadRole::Sergeant);
+        assign_to_squad(&mut world, squad, second, SquadRole::Sergeant);
+        let commander = |world: &World| world
+            .entry_ref(squad)
+            .unwrap()
+            .get_component::<SquadCommander>()
+            .ok()
+            .copied();
+        assert_eq!(commander(&world), Some(SquadCommander(second)));
+        world.remove(second);
+        missions_tick(&mut world);
+        assert_eq!(commander(&world), Some(SquadCommander(first)));
+        world.remove(first);
+        missions_tick(&mut world);
+        assert_eq!(commander(&world), None);
+    }
+}
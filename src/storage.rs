@@ -5,6 +5,7 @@ use std::collections::HashMap;
 use legion::*;
 
 use crate::area::*;
+use crate::content::*;
 use crate::core::*;
 use crate::resources::*;
 
@@ -41,22 +42,7 @@ impl Into<i32> for RealUnits {
 pub fn get_piece_size (
     resource: Resource,
 ) -> AreaOccupied {
-    match resource {
-        Resource::BioRaw           => AreaOccupied(10),
-        Resource::ScrapT1          => AreaOccupied(100),
-        Resource::ScrapT2          => AreaOccupied(100),
-        Resource::ScrapT3          => AreaOccupied(100),
-        Resource::Concrete         => AreaOccupied(100),
-        Resource::Slime            => AreaOccupied(10),
-        Resource::ComponentT1      => AreaOccupied(10),
-        Resource::ComponentT2      => AreaOccupied(10),
-        Resource::ComponentT3      => AreaOccupied(10),
-        Resource::ReagentT1        => AreaOccupied(10),
-        Resource::ReagentT2        => AreaOccupied(10),
-        Resource::ReagentT3        => AreaOccupied(10),
-        Resource::Polymer          => AreaOccupied(50),
-        Resource::Concentrat       => AreaOccupied(1),
-    }
+    AreaOccupied(CONTENT.piece(resource))
 }
 
 /// Куча ресурсов занимает столько места. Сколько там единиц ресурса
@@ -87,11 +73,19 @@ pub fn get_rooms_for_divisible_load(
     let mut content_query =
         <(&BelongsToRoom, &AreaOccupied)>::query();
     for (BelongsToRoom(room), occupied) in content_query.iter(world) {
-        rooms.entry(*room).and_modify (|free| { free.0 -= occupied.0});
+        rooms.entry(*room).and_modify (|free| { free.0 = free.0.saturating_sub(occupied.0)});
     };
     rooms
 }
 
+/// С чем колония начинает игру
+pub static INITIAL_STOCK: &[(Resource, usize)] = &[
+    (Resource::Concentrat, 1000),
+    (Resource::ScrapT1, 50),
+    (Resource::ScrapT2, 40),
+    (Resource::Polymer, 30),
+];
+
 /// Положить ресурс на хранение.
 /// Возвращает количество невместившегося ресурса.
 pub fn put_resource(
@@ -106,9 +100,12 @@ pub fn put_resource(
             world,
         );
     for (room, area) in rooms.iter () {
+        if amount_.0 == 0 { break };
         let required_space =
             piece_size.0 * amount_.0;
-        let to_put_here = min(area.0 as usize, required_space as usize);
+        // кладем только целые единицы ресурса
+        let to_put_here = min(area.0, required_space) / piece_size.0 * piece_size.0;
+        if to_put_here == 0 { continue };
         amount_.0 -= (to_put_here / piece_size.0) as usize;
         world.push(
             (
@@ -117,7 +114,6 @@ pub fn put_resource(
                 AreaOccupied(to_put_here),
             )
         );
-        if amount.0 <= 0 { break };
     };
     amount_
 }
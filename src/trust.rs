@@ -0,0 +1,371 @@
+use std::fmt;
+use std::collections::HashMap;
+
+use legion::*;
+use rand::thread_rng;
+use rand::Rng;
+
+use crate::core::*;
+use crate::resources::*;
+use crate::squad::*;
+use crate::storage::*;
+use crate::unrest::*;
+
+/// Предел доверия партии
+pub static TRUST_MAX: u8 = 100;
+
+/// С каким доверием начинает новый начальник блока
+pub static TRUST_START: u8 = 50;
+
+/// С какого доверия Главблок присылает снабжение
+pub static TRUST_SUPPLY: u8 = 80;
+
+/// Как часто приходит снабжение (смен)
+pub static SUPPLY_PERIOD: usize = 30;
+
+/// Сколько концентрата в одной поставке
+pub static SUPPLY_CONCENTRAT: usize = 100;
+
+/// Как часто Главблок спускает новую директиву (смен)
+pub static DIRECTIVE_PERIOD: usize = 20;
+
+/// Сколько директив может висеть одновременно
+pub static MAX_DIRECTIVES: usize = 2;
+
+/// Сколько доверия теряется за каждого умершего от голода
+pub static TRUST_PER_STARVED: u8 = 2;
+
+/// Сколько доверия теряется за каждого, кому отказали в убежище
+pub static TRUST_PER_REFUSED: u8 = 1;
+
+/// Сколько доверия теряется за каждого, кого поселили сверх вместимости
+pub static TRUST_PER_OVERFILLED: u8 = 1;
+
+/// Доверие партии к начальнику блока. На нуле начальника снимают.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PartyTrust(pub u8);
+
+impl Default for PartyTrust {
+    fn default() -> Self {
+        Self(TRUST_START)
+    }
+}
+
+/// Что требует директива
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirectiveGoal {
+    Deliver(Resource, RealUnits), // сдать ресурс
+    KeepSquad(SquadStandard), // держать укомплектованный отряд
+}
+
+impl fmt::Display for DirectiveGoal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DirectiveGoal::Deliver(res, amount) =>
+                write!(f, "Сдать {}: {}", res, amount.0),
+            DirectiveGoal::KeepSquad(standard) =>
+                write!(f, "Держать отряд {} в полном составе", standard),
+        }
+    }
+}
+
+/// Директива Главблока
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Directive {
+    pub goal: DirectiveGoal,
+    pub shifts_left: usize, // сколько смен осталось на выполнение
+    pub reward: u8, // сколько доверия за выполнение
+    pub penalty: u8, // сколько доверия за провал
+}
+
+/// Директивы на исполнении
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Directives {
+    pub active: Vec<Directive>,
+    pub next_in: usize, // через сколько смен придет следующая
+    pub supply_in: usize, // через сколько смен снабжение
+}
+
+impl Default for Directives {
+    fn default() -> Self {
+        Self {
+            active: Vec::new(),
+            next_in: DIRECTIVE_PERIOD,
+            supply_in: SUPPLY_PERIOD,
+        }
+    }
+}
+
+/// Начальника блока сняли с должности. Игра окончена.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameOver(pub String);
+
+/// Изменить доверие партии и записать причину в сводку
+pub fn change_trust(
+    resources: &mut Resources,
+    delta: i16,
+    reason: &str,
+) {
+    if delta == 0 {
+        return;
+    }
+    let trust = resources
+        .get::<PartyTrust>()
+        .map(|t| *t)
+        .unwrap_or_default();
+    let level = (trust.0 as i16 + delta).max(0).min(TRUST_MAX as i16) as u8;
+    resources.insert(PartyTrust(level));
+    report(resources, format!("Доверие партии {:+}: {}", delta, reason));
+}
+
+/// Сгенерировать директиву Главблока
+pub fn random_directive() -> Directive {
+    let mut rng = thread_rng();
+    match rng.gen_range(0..4) {
+        0 => Directive {
+            goal: DirectiveGoal::Deliver(Resource::ComponentT2, RealUnits(50)),
+            shifts_left: 10,
+            reward: 10,
+            penalty: 10,
+        },
+        1 => Directive {
+            goal: DirectiveGoal::Deliver(Resource::ComponentT1, RealUnits(100)),
+            shifts_left: 15,
+            reward: 5,
+            penalty: 5,
+        },
+        2 => Directive {
+            goal: DirectiveGoal::Deliver(Resource::Concentrat, RealUnits(200)),
+            shifts_left: 20,
+            reward: 5,
+            penalty: 5,
+        },
+        _ => Directive {
+            goal: DirectiveGoal::KeepSquad(SquadStandard::Standard1G),
+            shifts_left: 20,
+            reward: 10,
+            penalty: 15,
+        },
+    }
+}
+
+/// Есть ли в блоке укомплектованный отряд этого стандарта
+fn has_full_squad(
+    world: &World,
+    standard: SquadStandard,
+) -> bool {
+    all_squads(world)
+        .iter()
+        .any(|(_, _, s, _, _, missing)| *s == standard && missing.is_empty())
+}
+
+/// Сдать ресурсы по директиве.
+/// Директива снимается, доверие растет.
+pub fn fulfil_directive(
+    world: &mut World,
+    resources: &mut Resources,
+    index: usize,
+) -> Result<(), SamosborError> {
+    let mut directives = resources
+        .get::<Directives>()
+        .map(|d| d.clone())
+        .unwrap_or_default();
+    let directive = match directives.active.get(index) {
+        Some(d) => *d,
+        None => return Ok(()),
+    };
+    match directive.goal {
+        DirectiveGoal::Deliver(res, amount) => {
+            let bunch: HashMap<Resource, RealUnits> = [(res, amount)]
+                .iter()
+                .cloned()
+                .collect();
+            writeoff_bunch(world, bunch)?;
+        },
+        DirectiveGoal::KeepSquad(_) => return Ok(()),
+    }
+    directives.active.remove(index);
+    resources.insert(directives);
+    change_trust(
+        resources,
+        directive.reward as i16,
+        &format!("выполнена директива \"{}\"", directive.goal),
+    );
+    Ok(())
+}
+
+/// Партия за смену: сроки директив, новые директивы, снабжение,
+/// недовольство волнениями. На нуле доверия начальника снимают.
+/// Отряд по директиве проверяется в последнюю смену срока.
+pub fn trust_tick(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    let mut directives = resources
+        .get::<Directives>()
+        .map(|d| d.clone())
+        .unwrap_or_default();
+
+    let mut done = Vec::new();
+    let mut failed = Vec::new();
+    for directive in directives.active.iter_mut() {
+        directive.shifts_left = directive.shifts_left.saturating_sub(1);
+        if directive.shifts_left > 0 {
+            continue;
+        }
+        match directive.goal {
+            DirectiveGoal::KeepSquad(standard) if has_full_squad(world, standard) =>
+                done.push(*directive),
+            _ => failed.push(*directive),
+        }
+    }
+    directives.active.retain(|d| d.shifts_left > 0);
+
+    directives.next_in = directives.next_in.saturating_sub(1);
+    if directives.next_in == 0 {
+        directives.next_in = DIRECTIVE_PERIOD;
+        if directives.active.len() < MAX_DIRECTIVES {
+            let directive = random_directive();
+            report(resources, format!(
+                "Директива Главблока: {} за {} смен",
+                directive.goal,
+                directive.shifts_left,
+            ));
+            directives.active.push(directive);
+        }
+    }
+
+    for directive in done {
+        change_trust(
+            resources,
+            directive.reward as i16,
+            &format!("выполнена директива \"{}\"", directive.goal),
+        );
+    }
+    for directive in failed {
+        change_trust(
+            resources,
+            -(directive.penalty as i16),
+            &format!("провалена директива \"{}\"", directive.goal),
+        );
+    }
+
+    let unrest = resources
+        .get::<Unrest>()
+        .map(|u| *u)
+        .unwrap_or_default();
+    if unrest.level >= UNREST_LOOT {
+        change_trust(resources, -2, "беспорядки в блоке");
+    } else if unrest.level >= UNREST_STRIKE {
+        change_trust(resources, -1, "забастовки в блоке");
+    }
+
+    let trust = resources
+        .get::<PartyTrust>()
+        .map(|t| *t)
+        .unwrap_or_default();
+    directives.supply_in = directives.supply_in.saturating_sub(1);
+    if directives.supply_in == 0 {
+        directives.supply_in = SUPPLY_PERIOD;
+        if trust.0 >= TRUST_SUPPLY {
+            put_resource(world, Resource::Concentrat, RealUnits(SUPPLY_CONCENTRAT));
+            report(resources, format!(
+                "Главблок прислал снабжение: концентрат {}",
+                SUPPLY_CONCENTRAT,
+            ));
+        }
+    }
+    resources.insert(directives);
+
+    if trust.0 == 0 {
+        resources.insert(GameOver(
+            "Партия утратила доверие. Начальник блока снят с должности.".to_string(),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directive(goal: DirectiveGoal, shifts_left: usize) -> Directive {
+        Directive { goal, shifts_left, reward: 10, penalty: 15 }
+    }
+
+    #[test]
+    fn trust_stays_within_bounds() {
+        let mut resources = Resources::default();
+        change_trust(&mut resources, 0, "ничего");
+        assert!(resources.get::<PartyTrust>().is_none());
+        change_trust(&mut resources, -(TRUST_MAX as i16) * 2, "провал");
+        assert_eq!(*resources.get::<PartyTrust>().unwrap(), PartyTrust(0));
+        change_trust(&mut resources, (TRUST_MAX as i16) * 2, "успех");
+        assert_eq!(*resources.get::<PartyTrust>().unwrap(), PartyTrust(TRUST_MAX));
+    }
+
+    #[test]
+    fn expired_directive_costs_trust() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let goal = DirectiveGoal::Deliver(Resource::Polymer, RealUnits(10));
+        resources.insert(PartyTrust::default());
+        resources.insert(Directives {
+            active: vec![directive(goal, 2)],
+            next_in: DIRECTIVE_PERIOD,
+            supply_in: SUPPLY_PERIOD,
+        });
+        trust_tick(&mut world, &mut resources);
+        assert_eq!(resources.get::<Directives>().unwrap().active.len(), 1);
+        assert_eq!(*resources.get::<PartyTrust>().unwrap(), PartyTrust(TRUST_START));
+        trust_tick(&mut world, &mut resources);
+        assert!(resources.get::<Directives>().unwrap().active.is_empty());
+        assert_eq!(*resources.get::<PartyTrust>().unwrap(), PartyTrust(TRUST_START - 15));
+        assert!(resources.get::<GameOver>().is_none());
+    }
+
+    #[test]
+    fn squad_directive_fails_without_squad() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Directives {
+            active: vec![directive(DirectiveGoal::KeepSquad(SquadStandard::Standard1G), 1)],
+            next_in: DIRECTIVE_PERIOD,
+            supply_in: SUPPLY_PERIOD,
+        });
+        // отряд по директиве нельзя сдать досрочно
+        assert_eq!(fulfil_directive(&mut world, &mut resources, 0), Ok(()));
+        assert_eq!(resources.get::<Directives>().unwrap().active.len(), 1);
+        trust_tick(&mut world, &mut resources);
+        assert_eq!(*resources.get::<PartyTrust>().unwrap(), PartyTrust(TRUST_START - 15));
+    }
+
+    #[test]
+    fn unpaid_delivery_is_refused() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let goal = DirectiveGoal::Deliver(Resource::Polymer, RealUnits(10));
+        resources.insert(Directives {
+            active: vec![directive(goal, 5)],
+            next_in: DIRECTIVE_PERIOD,
+            supply_in: SUPPLY_PERIOD,
+        });
+        assert!(fulfil_directive(&mut world, &mut resources, 0).is_err());
+        assert_eq!(resources.get::<Directives>().unwrap().active.len(), 1);
+        assert!(resources.get::<PartyTrust>().is_none());
+    }
+
+    #[test]
+    fn unrest_costs_trust_and_zero_trust_ends_game() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(PartyTrust(2));
+        resources.insert(Unrest { level: UNREST_STRIKE, low_mood_shifts: 0, suppress: false });
+        trust_tick(&mut world, &mut resources);
+        assert_eq!(*resources.get::<PartyTrust>().unwrap(), PartyTrust(1));
+        assert!(resources.get::<GameOver>().is_none());
+        resources.insert(Unrest { level: UNREST_LOOT, low_mood_shifts: 0, suppress: false });
+        trust_tick(&mut world, &mut resources);
+        assert_eq!(*resources.get::<PartyTrust>().unwrap(), PartyTrust(0));
+        assert!(resources.get::<GameOver>().is_some());
+    }
+}
@@ -9,20 +9,116 @@ use crate::people::*;
 use crate::production::*;
 use crate::storage::*;
 use crate::resources::*;
+use crate::squad::*;
+use crate::items::*;
+use crate::recipes::*;
+use crate::mood::*;
+use crate::unrest::*;
+use crate::housing::*;
+use crate::experience::*;
+use crate::education::*;
+use crate::population::*;
+use crate::refugees::*;
+use crate::trust::*;
+use crate::quota::*;
+use crate::inspection::*;
+use crate::accounting::*;
+use crate::coupons::*;
+use crate::research::*;
 
 pub type BuildPowerPool = HashMap<Profession,HashMap<Tier, BuildPower>>;
 
+/// Сколько работы колония могла сделать за смену и сколько сделала
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LaborStats {
+    pub potential: BuildPower, // выработка, если бы все работали в обычную силу
+    pub actual: BuildPower, // выработка с учетом настроения и сытости
+    pub morale_lost: BuildPower, // недовыработка вышедших на смену из-за настроения и сытости
+    pub refused: usize, // сколько человек отказались работать
+    pub striking: usize, // сколько человек бастует
+    pub sick: usize, // сколько человек болеет
+    pub training: usize, // сколько человек учится
+}
+
+impl Default for LaborStats {
+    fn default() -> Self {
+        Self {
+            potential: BuildPower(0),
+            actual: BuildPower(0),
+            morale_lost: BuildPower(0),
+            refused: 0,
+            striking: 0,
+            sick: 0,
+            training: 0,
+        }
+    }
+}
+
+impl LaborStats {
+    /// Сколько трудочасов потеряно всего:
+    /// из-за морали, болезней, забастовок и учебы
+    pub fn lost(&self) -> BuildPower {
+        BuildPower(self.potential.0.saturating_sub(self.actual.0))
+    }
+}
+
 pub fn turn(
     world: &mut World,
     resources: &mut Resources,
 ) {
+    if resources.get::<GameOver>().is_some() {
+        return;
+    }
+    resources.insert(TurnReport::default());
+    run_standing_orders(world, resources);
+    distribute_items(world);
     calc_buildpower(world, resources);
+    school_tick(world, resources);
     process_tasks(world, resources);
+    experience_tick(world, resources);
+    let mut lost: Vec<(Resource, RealUnits)> = finish_batches(world).into_iter().collect();
+    if !lost.is_empty() {
+        lost.sort();
+        let lost: Vec<String> = lost
+            .iter()
+            .map(|(res, cnt)| format!("{} {}", res, cnt.0))
+            .collect();
+        report(resources, format!("Не влезло на склады и пропало: {}", lost.join(", ")));
+    }
+    for (student, prof) in finish_courses(world) {
+        let name = world
+            .entry_ref(student)
+            .ok()
+            .and_then(|entry| entry.get_component::<Name>().ok().cloned())
+            .map(|Name(name)| name)
+            .unwrap_or_default();
+        report(resources, format!("{} окончил(а) курсы: {}", name, prof));
+    }
+    finish_audits(world, resources);
+    finish_research(world, resources);
     hunger_tick(world, resources);
     consume_concentrat(world, resources);
+    coupons_tick(world, resources);
+    disease_tick(world);
+    mood_tick(world, resources);
+    unrest_tick(world, resources);
+    shrinkage_tick(world, resources);
+    wear_mission_equipment(world);
+    missions_tick(world);
+    grow_older(world);
+    population_tick(world, resources);
+    refugees_tick(resources);
+    quota_tick(world, resources);
+    inspections_tick(world, resources);
+    trust_tick(world, resources);
 }
 
-/// Сформировать пул билдпавера
+/// Сформировать пул билдпавера.
+/// Пул формируется заново каждую смену: неизрасходованные трудочасы сгорают.
+/// Инструмент на руках у рабочего увеличивает его выработку и изнашивается.
+/// Настроение и сытость меняют выработку, отчаявшиеся не работают,
+/// бастующие, больные и ученики тоже. Дети, пенсионеры,
+/// кладовщики и бойцы на задании в пул не работают вовсе.
 fn calc_buildpower(
     world: &mut World,
     resources: &mut Resources,
@@ -30,12 +126,69 @@ fn calc_buildpower(
     let mut buildpower_pool = resources
         .get_mut::<BuildPowerPool>()
         .unwrap();
+    // трудочасы не копятся: что не освоили за смену, то пропало
+    buildpower_pool.clear();
+    let equipped = equipped_items(world);
+    let mut tool_users = HashSet::new();
+    let mut stats = LaborStats::default();
+    let mut contributions = LaborContributions::new();
     let mut people_query = <(
+        Entity,
         &Profession,
         &Tier,
+        &Mood,
+        &Satiety,
+        Option<&OnStrike>,
+        Option<&Sick>,
+        Option<&InTraining>,
     )>::query();
-    for (prof, tier) in people_query.iter(world) {
-        let human_bp = tier2comrad_buildpower(tier.clone());
+    let mut idle_query = <Entity>::query()
+        .filter(component::<Child>() | component::<Retired>() | component::<Storekeeper>());
+    let mut idle: HashSet<Entity> = idle_query.iter(world).copied().collect();
+    // бойцы на задании в блоке не работают
+    idle.extend(fighters_on_mission(world));
+    for (entity, prof, tier, mood, sat, strike, sick, training) in people_query.iter(world) {
+        if idle.contains(entity) {
+            continue;
+        }
+        let mut human_bp = tier2comrad_buildpower(*tier);
+        if let Some((_, _, tool_tier, _)) = equipped
+            .get(entity)
+            .and_then(|items| items.iter().find(|(_, item, _, _)| *item == Item::Toolkit))
+        {
+            human_bp = BuildPower((human_bp.0 as f32 * toolkit_coef(*tool_tier)) as usize);
+        }
+        stats.potential += human_bp;
+        if training.is_some() {
+            stats.training += 1;
+            continue;
+        }
+        if sick.is_some() {
+            stats.sick += 1;
+            continue;
+        }
+        if strike.is_some() {
+            stats.striking += 1;
+            continue;
+        }
+        let coef = morale_coef(*mood, *sat);
+        if coef == 0.0 {
+            stats.refused += 1;
+            stats.morale_lost += human_bp;
+            continue;
+        }
+        let full_bp = human_bp;
+        let human_bp = BuildPower((human_bp.0 as f32 * coef) as usize);
+        stats.actual += human_bp;
+        stats.morale_lost += BuildPower(full_bp.0.saturating_sub(human_bp.0));
+        contributions.insert(*entity, (*prof, *tier, human_bp));
+        if equipped
+            .get(entity)
+            .map(|items| items.iter().any(|(_, item, _, _)| *item == Item::Toolkit))
+            .unwrap_or(false)
+        {
+            tool_users.insert(*entity);
+        }
         let by_tier_hm = buildpower_pool
             .entry(*prof)
             .or_insert(HashMap::new());
@@ -45,13 +198,54 @@ fn calc_buildpower(
             .or_insert(BuildPower(0));
         *bp += human_bp;
     }
+    drop(buildpower_pool);
+    resources.insert(stats);
+    resources.insert(contributions);
+    wear_items(world, &tool_users, &[Item::Toolkit]);
 }
 
-/// Распределить все очки работы по заданиям
-/// TODO: Наивная реализация. T3 инженеры на T3
-/// станках тоже должны уметь делать T1 задания, причем
+/// Опыт за освоенные трудочасы и повышения разряда
+fn experience_tick(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    let contributions = resources
+        .get::<LaborContributions>()
+        .map(|c| c.clone())
+        .unwrap_or_default();
+    let pool_left = resources
+        .get::<BuildPowerPool>()
+        .map(|p| p.clone())
+        .unwrap_or_default();
+    gain_experience(world, &contributions, &pool_left);
+    for (comrad, prof, tier) in promotions_tick(world) {
+        let name = world
+            .entry_ref(comrad)
+            .ok()
+            .and_then(|entry| entry.get_component::<Name>().ok().cloned())
+            .map(|Name(name)| name)
+            .unwrap_or_default();
+        report(resources, format!("{} {}: повышение до {}", prof, name, tier));
+    }
+}
+
+/// Снаряжение бойцов на задании изнашивается
+fn wear_mission_equipment(
+    world: &mut World,
+) {
+    let fighters = fighters_on_mission(world);
+    wear_items(
+        world,
+        &fighters,
+        &[Item::Flamethrower, Item::Rake, Item::ProtectiveSuit],
+    );
+}
+
+/// Распределить все очки работы по заданиям.
+/// Более опытные работники берутся за задания попроще,
+/// если людей нужного разряда не хватило.
+/// TODO: T3 инженеры на T3 станках должны делать T1 задания
 /// более эффективно чем T1 работяги на T1 станках.
-/// Надо писать правила деградации.
 pub fn process_tasks(
     world: &mut World,
     resources: &mut Resources,
@@ -94,26 +288,30 @@ pub fn process_tasks(
             // мощность станка которая у нас есть.
             let mut stat_bp_ = BuildPower(0);
             let stat_bp = stationaries.get_mut(stationary).unwrap_or(&mut stat_bp_);
-            // мощность человечков, которая у нас есть
-            let mut ppl_bp_ = BuildPower(0);
+            // мощность человечков, которая у нас есть.
+            // Сначала работают люди нужного разряда, потом более опытные.
             let mut acc_ = HashMap::new();
-            let ppl_bp = buildpower_pool
+            let by_tier = buildpower_pool
                 .get_mut(prof)
-                .unwrap_or(&mut acc_)
-                .get_mut(tier)
-                .unwrap_or(&mut ppl_bp_);
-            // Какую по факту силу мы можем освоить
-            let bp_to_withdraw = min(
-                stat_bp.clone(),
-                min(
-                    ppl_bp.clone(),
-                    bp.clone(),
-                )
-            );
-            *stat_bp -= bp_to_withdraw;
-            *ppl_bp -= bp_to_withdraw;
-            *bp -= bp_to_withdraw;
-            progress.bp_invested += bp_to_withdraw;
+                .unwrap_or(&mut acc_);
+            for ppl_tier in [Tier::T1, Tier::T2, Tier::T3].iter().filter(|t| *t >= tier) {
+                let mut ppl_bp_ = BuildPower(0);
+                let ppl_bp = by_tier
+                    .get_mut(ppl_tier)
+                    .unwrap_or(&mut ppl_bp_);
+                // Какую по факту силу мы можем освоить
+                let bp_to_withdraw = min(
+                    *stat_bp,
+                    min(
+                        *ppl_bp,
+                        *bp,
+                    )
+                );
+                *stat_bp -= bp_to_withdraw;
+                *ppl_bp -= bp_to_withdraw;
+                *bp -= bp_to_withdraw;
+                progress.bp_invested += bp_to_withdraw;
+            }
             if progress.bp_invested >= progress.bp_required {
                 *status = TaskStatus::Ready;
                 delete_progresses.insert(entity.clone());
@@ -125,74 +323,84 @@ pub fn process_tasks(
     }
 }
 
-/// Голод
+/// Голод. Сытость падает на 10 за смену, на нуле голодная смерть.
+/// Партия голодных смертей не прощает.
 pub fn hunger_tick(
     world: &mut World,
-    _resources: &mut Resources,
+    resources: &mut Resources,
 ) {
     let mut died_by_hunger: Vec<Entity> = Vec::new();
     let mut query = <(
         Entity,
         &mut Satiety,
-        &mut Mood,
     )>::query();
-    for (entity, sat, mood) in query.iter_mut(world) {
-        sat.0 -= 10;
-        if sat.0 < 10 {
-            died_by_hunger.push(*entity);
-        }
-        // ниже ста - голод - минус настроение
-        if sat.0 < 100 {
-            mood.0.checked_sub(1);
+    for (entity, sat) in query.iter_mut(world) {
+        match starve_shift(*sat) {
+            None => {
+                *sat = Satiety(0);
+                died_by_hunger.push(*entity);
+            },
+            Some(s) => *sat = s,
         }
     }
     for e in died_by_hunger.iter() {
         world.remove(*e);
     }
+    remember_deaths(resources, died_by_hunger.len());
+    if !died_by_hunger.is_empty() {
+        report(resources, format!("Умерло от голода: {} чел", died_by_hunger.len()));
+        change_trust(
+            resources,
+            -((died_by_hunger.len() * TRUST_PER_STARVED as usize).min(TRUST_MAX as usize) as i16),
+            "голодная смерть",
+        );
+    }
 }
 
-/// Люди едят концентрат
+/// Люди едят концентрат.
+/// Кто сколько получит решает пайковая политика.
 pub fn consume_concentrat(
     world: &mut World,
-    _resources: &mut Resources,
+    resources: &mut Resources,
 ) {
+    let policy = resources
+        .get::<RationPolicy>()
+        .map(|p| *p)
+        .unwrap_or(RationPolicy::Full);
     // сколько есть на складе
-    let mut t1_conc_amount = how_much_we_have(
+    let t1_conc_amount = how_much_we_have(
         world,
         Resource::Concentrat,
     );
-    // Сколько выдано
-    let mut t1_conc_writeroff = 0;
     // имеет настроение = человек.
     // да, знаю, зашибись признак.
+    // Не дали пожрать - настроение ухудшится через сытость.
     let mut query = <(
-        &mut Mood,
+        &Profession,
+        &Tier,
         &mut Satiety,
     )>::query();
+    let mut eaters: Vec<(Profession, Tier, &mut Satiety)> = query
+        .iter_mut(world)
+        .map(|(p, t, s)| (*p, *t, s))
+        .collect();
+    let snapshot: Vec<(Profession, Tier, Satiety)> = eaters
+        .iter()
+        .map(|(p, t, s)| (*p, *t, **s))
+        .collect();
+    let plan = ration_plan(&snapshot, t1_conc_amount.0, policy);
 
-    for (mood, sat) in query.iter_mut(world){
-        if t1_conc_amount.0 <= 0 {
-            // Не дали пожрать. Настроение
-            // от такого ухудшается.
-            mood.0.checked_sub(1);
-        } else {
-            t1_conc_amount.0 -= 1;
-            t1_conc_writeroff += 1;
-            mood.0 += 1;
-            sat.0 += 10;
+    // Сколько выдано
+    let mut t1_conc_writeroff = 0;
+    for ((_, _, sat), packs) in eaters.iter_mut().zip(plan.iter()) {
+        for _ in 0..*packs {
+            **sat = eat_pack(**sat);
         }
+        t1_conc_writeroff += packs;
     }
-    let rest = writeoff(
+    writeoff(
         world,
         Resource::Concentrat,
         RealUnits(t1_conc_writeroff),
     );
-    // не получилось списать все. Ну ок, списываем что есть.
-    if rest.0 != 0 {
-        writeoff(
-            world,
-            Resource::Concentrat,
-            rest,
-        );
-    }
 }
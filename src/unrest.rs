@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use legion::*;
+
+use crate::accounting::*;
+use crate::core::*;
+use crate::people::*;
+use crate::resources::*;
+use crate::storage::*;
+
+/// Наибольший уровень волнений
+pub static UNREST_MAX: u8 = 10;
+
+/// Среднее настроение ниже этого считается плохим
+pub static UNREST_MOOD: u8 = 4;
+
+/// Сколько смен подряд плохого настроения нужно, чтобы волнения начали расти
+pub static UNREST_SHIFTS: usize = 3;
+
+/// С какого уровня волнений начинаются забастовки
+pub static UNREST_STRIKE: u8 = 3;
+
+/// С какого уровня волнений начинают грабить склады
+pub static UNREST_LOOT: u8 = 5;
+
+/// С какого уровня волнений люди бегут в другие блоки
+pub static UNREST_DESERT: u8 = 7;
+
+/// Сколько партийцев и ликвидаторов нужно, чтобы гасить лишний уровень волнений за смену
+pub static SUPPRESSORS_PER_LEVEL: usize = 10;
+
+/// Волнения в блоке
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Unrest {
+    pub level: u8, // 0..=UNREST_MAX
+    pub low_mood_shifts: usize, // сколько смен подряд среднее настроение плохое
+    pub suppress: bool, // приказ подавлять волнения силами партии и ликвидаторов
+}
+
+/// Комрад бастует и не работает
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OnStrike;
+
+/// Что натворили недовольные за прошлую смену
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct UnrestEvents {
+    pub striking: usize,
+    pub looted: HashMap<Resource, RealUnits>,
+    pub deserted: usize,
+}
+
+/// Кто может подавлять волнения
+pub fn is_suppressor(
+    prof: Profession,
+) -> bool {
+    prof == Profession::Party || prof == Profession::Likvidator
+}
+
+/// Как подавление волнений бьет по настроению.
+/// Давят только если есть кем и есть что.
+pub fn repression_mood_factor(
+    unrest: Unrest,
+    suppressors: usize,
+) -> i8 {
+    if unrest.suppress && unrest.level > 0 && suppressors > 0 { -2 } else { 0 }
+}
+
+/// На сколько уровней за смену подавят волнения
+pub fn suppression_power(
+    suppressors: usize,
+) -> u8 {
+    if suppressors == 0 {
+        0
+    } else {
+        (1 + suppressors / SUPPRESSORS_PER_LEVEL).min(UNREST_MAX as usize) as u8
+    }
+}
+
+/// Растут ли волнения, если среднее настроение такое
+fn next_unrest(
+    unrest: Unrest,
+    avg_mood: u8,
+) -> Unrest {
+    let mut result = unrest;
+    if avg_mood < UNREST_MOOD {
+        result.low_mood_shifts += 1;
+        if result.low_mood_shifts >= UNREST_SHIFTS {
+            result.level = (result.level + 1).min(UNREST_MAX);
+        }
+    } else {
+        result.low_mood_shifts = 0;
+        result.level = result.level.saturating_sub(1);
+    }
+    result
+}
+
+/// Волнения за смену: растут от плохого настроения, гасятся подавлением,
+/// а дальше недовольные бастуют, грабят склады и бегут из блока.
+pub fn unrest_tick(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    let unrest = resources
+        .get::<Unrest>()
+        .map(|u| *u)
+        .unwrap_or_default();
+
+    let mut people_query = <&Profession>::query();
+    let people = people_query.iter(world).count();
+    let suppressors = people_query
+        .iter(world)
+        .filter(|p| is_suppressor(**p))
+        .count();
+    let avg_mood = block_mood(world)
+        .checked_div(people)
+        .unwrap_or(UNREST_MOOD as usize) as u8;
+
+    let mut unrest = next_unrest(unrest, avg_mood);
+    if unrest.suppress {
+        unrest.level = unrest.level.saturating_sub(suppression_power(suppressors));
+    }
+
+    // кто недоволен
+    let mut unhappy: Vec<(Entity, Mood)> = Vec::new();
+    let mut query = <(Entity, &Profession, &Mood)>::query();
+    for (entity, prof, mood) in query.iter(world) {
+        if !is_suppressor(*prof) && mood.0 < UNREST_MOOD {
+            unhappy.push((*entity, *mood));
+        }
+    }
+
+    // забастовки
+    let mut strike_query = <(Entity, &OnStrike)>::query();
+    let was_striking: Vec<Entity> = strike_query
+        .iter(world)
+        .map(|(e, _)| *e)
+        .collect();
+    for e in was_striking {
+        if let Some(mut entry) = world.entry(e) {
+            entry.remove_component::<OnStrike>();
+        }
+    }
+    let mut events = UnrestEvents::default();
+    if unrest.level >= UNREST_STRIKE {
+        for (e, _) in unhappy.iter() {
+            if _d(1, 10) < unrest.level as usize {
+                if let Some(mut entry) = world.entry(*e) {
+                    entry.add_component(OnStrike);
+                    events.striking += 1;
+                }
+            }
+        }
+    }
+
+    // грабежи
+    if unrest.level >= UNREST_LOOT {
+        for (res, amount) in what_we_have(world) {
+            let loot = RealUnits(amount.0 * unrest.level as usize / 100);
+            if loot.0 > 0 {
+                let loot = lose_from_storage(world, resources, res, loot);
+                events.looted.insert(res, loot);
+            }
+        }
+    }
+
+    // бегство
+    if unrest.level >= UNREST_DESERT {
+        for (e, mood) in unhappy.iter() {
+            if mood.0 < UNREST_MOOD / 2 && _d(1, 10) == 0 {
+                world.remove(*e);
+                events.deserted += 1;
+            }
+        }
+    }
+
+    if events.deserted > 0 {
+        report(resources, format!("Сбежало из блока: {} чел", events.deserted));
+    }
+    if !events.looted.is_empty() {
+        report(resources, "Недовольные разграбили склады".to_string());
+    }
+    resources.insert(unrest);
+    resources.insert(events);
+}
+
+/// Сколько сейчас бастует
+pub fn strikers_count(
+    world: &World,
+) -> usize {
+    let mut query = <&OnStrike>::query();
+    query.iter(world).count()
+}
+
+/// Сколько людей может подавлять волнения
+pub fn suppressors_count(
+    world: &World,
+) -> usize {
+    let mut query = <&Profession>::query();
+    query.iter(world).filter(|p| is_suppressor(**p)).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::area::*;
+    use crate::production::*;
+
+    #[test]
+    fn unrest_grows_after_several_bad_shifts() {
+        let mut unrest = Unrest::default();
+        for _ in 1..UNREST_SHIFTS {
+            unrest = next_unrest(unrest, UNREST_MOOD - 1);
+            assert_eq!(unrest.level, 0);
+        }
+        unrest = next_unrest(unrest, UNREST_MOOD - 1);
+        assert_eq!(unrest.level, 1);
+        unrest = next_unrest(unrest, UNREST_MOOD - 1);
+        assert_eq!(unrest.level, 2);
+        // хорошая смена сбрасывает счетчик и гасит уровень
+        unrest = next_unrest(unrest, UNREST_MOOD);
+        assert_eq!(unrest.low_mood_shifts, 0);
+        assert_eq!(unrest.level, 1);
+        unrest = next_unrest(unrest, UNREST_MOOD - 1);
+        assert_eq!(unrest.level, 1);
+    }
+
+    #[test]
+    fn unrest_is_capped() {
+        let unrest = Unrest { level: UNREST_MAX, low_mood_shifts: UNREST_SHIFTS, suppress: false };
+        assert_eq!(next_unrest(unrest, 0).level, UNREST_MAX);
+        assert_eq!(next_unrest(Unrest::default(), UNREST_MOOD).level, 0);
+    }
+
+    #[test]
+    fn suppression_needs_people() {
+        assert_eq!(suppression_power(0), 0);
+        assert_eq!(suppression_power(1), 1);
+        assert_eq!(suppression_power(SUPPRESSORS_PER_LEVEL), 2);
+        let unrest = Unrest { level: 1, low_mood_shifts: 0, suppress: true };
+        assert_eq!(repression_mood_factor(unrest, 1), -2);
+        assert_eq!(repression_mood_factor(unrest, 0), 0);
+        assert_eq!(repression_mood_factor(Unrest { suppress: false, ..unrest }, 1), 0);
+        assert_eq!(repression_mood_factor(Unrest { level: 0, ..unrest }, 1), 0);
+    }
+
+    #[test]
+    fn riot_strikes_and_loots() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let room = install_germ(&mut world, Germ::GermT2, AreaType::Party);
+        if let Some(mut entry) = world.entry(room) {
+            entry.add_component(TaskStatus::Ready);
+        }
+        put_resource(&mut world, Resource::Polymer, RealUnits(100));
+        let worker = spawn_comrad(&mut world, Profession::Worker, Tier::T1, room);
+        let party = spawn_comrad(&mut world, Profession::Party, Tier::T1, room);
+        for e in [worker, party].iter() {
+            if let Some(mut entry) = world.entry(*e) {
+                entry.add_component(Mood(UNREST_MOOD - 1));
+            }
+        }
+        resources.insert(Unrest { level: UNREST_MAX, low_mood_shifts: UNREST_SHIFTS, suppress: false });
+
+        unrest_tick(&mut world, &mut resources);
+
+        let events = resources.get::<UnrestEvents>().unwrap().clone();
+        assert_eq!(events.striking, 1);
+        assert_eq!(strikers_count(&world), 1);
+        assert!(world.entry(worker).unwrap().get_component::<OnStrike>().is_ok());
+        assert!(world.entry(party).unwrap().get_component::<OnStrike>().is_err());
+        assert_eq!(events.looted.get(&Resource::Polymer), Some(&RealUnits(10)));
+        assert_eq!(resources.get::<Shrinkage>().unwrap().0.get(&Resource::Polymer), Some(&RealUnits(10)));
+        assert_eq!(events.deserted, 0);
+    }
+}